@@ -1,3 +1,4 @@
 fn main() {
+    #[cfg(feature = "gui")]
     slint_build::compile("ui/ui.slint").unwrap();
 }
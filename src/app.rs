@@ -0,0 +1,1477 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use async_std::channel::Sender;
+use async_std::{channel, task};
+use liando::calendar::{self, SpecialDay};
+use liando::highlight::{self, HighlightRule};
+use liando::import::{undo_last_import, update_punches, update_record, update_statistics};
+use liando::input::{parse_col, SheetConfig, UserInput};
+use liando::keyword::{self, KeywordRule};
+use liando::overtime::OvertimeRule;
+use liando::punch::{format_minutes, parse_minutes, Shift};
+use liando::report::{
+    default_day_columns, default_output_name, generate_report, render_header, sheet_names,
+    DayColumn, DayField, Rounding, StylePreset, WorkTime, WorkTimeUnit,
+};
+use rfd::{AsyncFileDialog, MessageDialog, MessageLevel};
+use sled::Db;
+use slint::{ComponentHandle, Model, ModelRc, PhysicalPosition, SharedString, VecModel};
+use time::{macros::format_description, Date};
+use umya_spreadsheet::helper::coordinate::string_from_column_index;
+
+use crate::{
+    CalendarDay, DayColumnConfig, HighlightRuleConfig, KeywordRuleConfig, Logic, TemplateConfig, Ui,
+};
+
+macro_rules! parse_input_col {
+    ($ui:ident, $get:ident, $set:ident) => {{
+        let old_value = $ui.global::<Logic>().$get();
+        if let Some(int_value) = parse_col(&old_value) {
+            let new_value = string_from_column_index(&int_value).into();
+            if old_value.ne(&new_value) {
+                $ui.global::<Logic>().$set(new_value);
+            }
+            Some(int_value)
+        } else {
+            None
+        }
+    }};
+}
+
+macro_rules! parse_input_row {
+    ($ui:ident, $get:ident, $set:ident) => {{
+        let old_value = $ui.global::<Logic>().$get();
+        let int_value = old_value.parse::<u32>().unwrap_or_default();
+        if int_value < 1 {
+            None
+        } else {
+            let new_value = int_value.to_string().into();
+            if old_value.ne(&new_value) {
+                $ui.global::<Logic>().$set(new_value);
+            }
+            Some(int_value)
+        }
+    }};
+}
+
+pub(crate) struct App {
+    ui: Ui,
+    db: Db,
+}
+
+impl App {
+    pub(crate) fn new(db: Db) -> Result<Self> {
+        Ok(App { ui: Ui::new()?, db })
+    }
+
+    pub fn run(&self) -> Result<()> {
+        self.init()?;
+
+        self.ui
+            .window()
+            .set_position(PhysicalPosition::new(520, 520));
+        self.ui.run()?;
+
+        Ok(())
+    }
+
+    fn init(&self) -> Result<()> {
+        self.init_input()?;
+        self.on_statistics_file_select();
+        self.on_record_file_select();
+        self.on_punch_file_select();
+        self.on_import_undo_clicked();
+        self.on_template_remove_clicked();
+        self.on_template_push_clicked();
+        self.on_template_sheets_load_clicked();
+        self.on_day_columns_edit_clicked();
+        self.on_day_column_remove_clicked();
+        self.on_day_column_up_clicked();
+        self.on_day_column_push_clicked();
+        self.on_calendar_import_clicked();
+        self.on_calendar_save_clicked();
+        self.on_calendar_remove_clicked();
+        self.on_calendar_push_clicked();
+        self.on_keyword_save_clicked();
+        self.on_keyword_reset_clicked();
+        self.on_keyword_remove_clicked();
+        self.on_keyword_push_clicked();
+        self.on_highlight_save_clicked();
+        self.on_highlight_reset_clicked();
+        self.on_highlight_remove_clicked();
+        self.on_highlight_push_clicked();
+        self.on_execute_clicked();
+
+        Ok(())
+    }
+
+    fn init_input(&self) -> Result<()> {
+        let user_input = UserInput::load(&self.db)?;
+
+        self.ui
+            .global::<Logic>()
+            .set_start_date(long_date_string(user_input.start_date));
+        self.ui
+            .global::<Logic>()
+            .set_end_date(long_date_string(user_input.end_date));
+        set_input_cols(&self.ui, &user_input);
+        self.ui
+            .global::<Logic>()
+            .set_statistics_start_row(user_input.statistics_start_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_header_row(user_input.statistics_header_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_employee_id_header(user_input.statistics_employee_id_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_date_header(user_input.statistics_date_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_enter_result_header(user_input.statistics_enter_result_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_leave_result_header(user_input.statistics_leave_result_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_statistics_work_minutes_header(user_input.statistics_work_minutes_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_record_start_row(user_input.record_start_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_record_header_row(user_input.record_header_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_record_employee_id_header(user_input.record_employee_id_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_record_date_header(user_input.record_date_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_record_abnormal_reason_header(user_input.record_abnormal_reason_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_punch_start_row(user_input.punch_start_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_punch_header_row(user_input.punch_header_row.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_punch_employee_id_header(user_input.punch_employee_id_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_punch_time_header(user_input.punch_time_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_punch_device_header(user_input.punch_device_header.into());
+        self.ui
+            .global::<Logic>()
+            .set_shift_start(format_minutes(user_input.shift.start).into());
+        self.ui
+            .global::<Logic>()
+            .set_shift_end(format_minutes(user_input.shift.end).into());
+        self.ui
+            .global::<Logic>()
+            .set_lunch_start(format_minutes(user_input.shift.lunch_start).into());
+        self.ui
+            .global::<Logic>()
+            .set_lunch_end(format_minutes(user_input.shift.lunch_end).into());
+        self.ui
+            .global::<Logic>()
+            .set_summary_columns(user_input.summary_columns);
+        self.ui
+            .global::<Logic>()
+            .set_style_presets(ModelRc::new(VecModel::from(
+                StylePreset::NAMES.map(SharedString::from).to_vec(),
+            )));
+        self.ui
+            .global::<Logic>()
+            .set_style_preset(user_input.style_preset.name().into());
+        self.ui
+            .global::<Logic>()
+            .set_clear_block(user_input.clear_block);
+        self.ui
+            .global::<Logic>()
+            .set_no_data_text(user_input.no_data_text.into());
+        self.ui
+            .global::<Logic>()
+            .set_overtime_enabled(user_input.overtime.enabled);
+        self.ui
+            .global::<Logic>()
+            .set_overtime_workday_hours(user_input.overtime.workday_hours.to_string().into());
+        self.ui
+            .global::<Logic>()
+            .set_overtime_restday_hours(user_input.overtime.restday_hours.to_string().into());
+
+        let template_cfg = user_input
+            .template_cfg
+            .iter()
+            .fold(Vec::new(), |mut cfg, value| {
+                cfg.push(TemplateConfig {
+                    template_employee_id_col: string_from_column_index(&value.employee_id_col)
+                        .into(),
+                    template_start_col: string_from_column_index(&value.start_col).into(),
+                    template_title_row: value.title_row.to_string().into(),
+                    template_sheet_name: sheet_name_str(&value.sheet_name).into(),
+                    day_columns: day_column_configs(&value.day_columns),
+                    work_time_unit: value.work_time.unit.name().into(),
+                    rounding: value.work_time.rounding.name().into(),
+                    precision: value.work_time.precision.to_string().into(),
+                    standard_hours: value.work_time.standard_hours.to_string().into(),
+                });
+                cfg
+            });
+        self.ui
+            .global::<Logic>()
+            .set_template_configs(ModelRc::new(VecModel::from(template_cfg)));
+        set_template_sheet_names(&self.ui, Vec::new());
+        self.ui
+            .global::<Logic>()
+            .set_day_fields(ModelRc::new(VecModel::from(
+                DayField::NAMES.map(SharedString::from).to_vec(),
+            )));
+        self.ui
+            .global::<Logic>()
+            .set_work_time_units(ModelRc::new(VecModel::from(
+                WorkTimeUnit::NAMES.map(SharedString::from).to_vec(),
+            )));
+        self.ui
+            .global::<Logic>()
+            .set_roundings(ModelRc::new(VecModel::from(
+                Rounding::NAMES.map(SharedString::from).to_vec(),
+            )));
+
+        set_keyword_rules(&self.ui, &keyword::load(&self.db)?);
+        set_highlight_rules(&self.ui, &highlight::load(&self.db)?);
+
+        match calendar::load(&self.db) {
+            Ok(days) => set_calendar(&self.ui, &days),
+            Err(e) => {
+                // 界面显示后才能弹窗，放到事件循环中
+                let text = SharedString::from(format!("读取工作日历失败：{e}"));
+                self.ui
+                    .as_weak()
+                    .upgrade_in_event_loop(move |ui| {
+                        ui.set_alert_text(text);
+                        ui.invoke_alert();
+                    })
+                    .ok();
+            }
+        }
+        Ok(())
+    }
+
+    fn on_statistics_file_select(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui
+            .global::<Logic>()
+            .on_statistics_import_clicked(move || {
+                let ui_weak_copy1 = ui_weak.clone();
+                let ui_weak_copy2 = ui_weak.clone();
+                let db = db.clone();
+                task::spawn(async move {
+                    let mut res = Ok(());
+                    if let Some(mut user_input) = get_input(ui_weak_copy1).await {
+                        if let Some(file) =
+                            select_file("请选择每日统计表", &["xls", "xlsx", "csv"]).await
+                        {
+                            // 保存输入，并导入上下班情况和工作时长到sled
+                            res = update_statistics(file, &mut user_input, &db).map(|summary| {
+                                show_input_cols(ui_weak_copy2.clone(), user_input);
+                                show_info(ui_weak_copy2.clone(), summary.to_string());
+                            });
+                        }
+                    }
+                    reset_button(ui_weak_copy2, res);
+                });
+            });
+    }
+
+    fn on_record_file_select(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_record_import_clicked(move || {
+            let ui_weak1 = ui_weak.clone();
+            let ui_weak2 = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let mut res = Ok(());
+                if let Some(mut user_input) = get_input(ui_weak1).await {
+                    if let Some(file) =
+                        select_file("请选择原始记录表", &["xls", "xlsx", "csv"]).await
+                    {
+                        // 保存输入，并导入考勤异常原因到sled
+                        res = update_record(file, &mut user_input, &db).map(|summary| {
+                            show_input_cols(ui_weak2.clone(), user_input);
+                            show_info(ui_weak2.clone(), summary.to_string());
+                        });
+                    }
+                }
+                reset_button(ui_weak2, res);
+            });
+        });
+    }
+
+    fn on_punch_file_select(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_punch_import_clicked(move || {
+            let ui_weak1 = ui_weak.clone();
+            let ui_weak2 = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let mut res = Ok(());
+                if let Some(mut user_input) = get_input(ui_weak1).await {
+                    if let Some(file) = select_file("请选择打卡记录", &["xls", "xlsx", "csv"]).await
+                    {
+                        // 保存输入，导入每次打卡到sled，并按班次计算工作时长
+                        res = update_punches(file, &mut user_input, &db).map(|summary| {
+                            show_input_cols(ui_weak2.clone(), user_input);
+                            show_info(ui_weak2.clone(), summary.to_string());
+                        });
+                    }
+                }
+                reset_button(ui_weak2, res);
+            });
+        });
+    }
+
+    fn on_import_undo_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_import_undo_clicked(move || {
+            let ui_weak = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let res = undo_last_import(&db).map(|changeset| {
+                    let text = match changeset {
+                        Some(changeset) => format!("已撤销：{changeset}"),
+                        None => "没有可撤销的导入".to_string(),
+                    };
+                    show_info(ui_weak.clone(), text);
+                });
+                reset_button(ui_weak, res);
+            });
+        });
+    }
+
+    fn on_template_remove_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_template_remove_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        store_day_columns(&ui);
+                        let mut template_cfg = ui
+                            .global::<Logic>()
+                            .get_template_configs()
+                            .iter()
+                            .collect::<Vec<TemplateConfig>>();
+                        template_cfg.remove(index as usize);
+
+                        // 正在编辑的每日列跟着调整序号，删掉的就不再编辑
+                        let editing = ui.global::<Logic>().get_day_columns_index();
+                        if editing == index {
+                            ui.global::<Logic>().set_day_columns_index(-1);
+                        } else if editing > index {
+                            ui.global::<Logic>().set_day_columns_index(editing - 1);
+                        }
+
+                        ui.global::<Logic>()
+                            .set_template_configs(ModelRc::new(VecModel::from(template_cfg)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_template_push_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui.global::<Logic>().on_template_push_clicked(move || {
+            ui_weak
+                .upgrade_in_event_loop(move |ui| {
+                    let mut template_cfg = ui
+                        .global::<Logic>()
+                        .get_template_configs()
+                        .iter()
+                        .collect::<Vec<TemplateConfig>>();
+                    let work_time = WorkTime::default();
+                    template_cfg.push(TemplateConfig {
+                        template_sheet_name: SHEET_BY_ORDER.into(),
+                        day_columns: day_column_configs(&default_day_columns()),
+                        work_time_unit: work_time.unit.name().into(),
+                        rounding: work_time.rounding.name().into(),
+                        precision: work_time.precision.to_string().into(),
+                        standard_hours: work_time.standard_hours.to_string().into(),
+                        ..Default::default()
+                    });
+
+                    ui.global::<Logic>()
+                        .set_template_configs(ModelRc::new(VecModel::from(template_cfg)));
+                    ui.global::<Logic>().set_button_enabled(true);
+                })
+                .ok();
+        });
+    }
+
+    fn on_template_sheets_load_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_template_sheets_load_clicked(move || {
+                let ui_weak = ui_weak.clone();
+                task::spawn(async move {
+                    let mut res = Ok(());
+                    if let Some(file) = select_file("请选择模板", &["xlsx"]).await {
+                        res = sheet_names(file).map(|names| {
+                            ui_weak
+                                .upgrade_in_event_loop(move |ui| {
+                                    set_template_sheet_names(&ui, names)
+                                })
+                                .ok();
+                        });
+                    }
+                    reset_button(ui_weak, res);
+                });
+            });
+    }
+
+    fn on_day_columns_edit_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_day_columns_edit_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        // 先把正在编辑的每日列存回原来的模板配置
+                        store_day_columns(&ui);
+                        if let Some(cfg) = ui
+                            .global::<Logic>()
+                            .get_template_configs()
+                            .row_data(index as usize)
+                        {
+                            let columns = cfg.day_columns.iter().collect::<Vec<_>>();
+                            ui.global::<Logic>()
+                                .set_day_columns(ModelRc::new(VecModel::from(columns)));
+                            ui.global::<Logic>().set_day_columns_index(index);
+                        }
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_day_column_remove_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_day_column_remove_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut columns = ui
+                            .global::<Logic>()
+                            .get_day_columns()
+                            .iter()
+                            .collect::<Vec<_>>();
+                        columns.remove(index as usize);
+
+                        ui.global::<Logic>()
+                            .set_day_columns(ModelRc::new(VecModel::from(columns)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_day_column_up_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_day_column_up_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut columns = ui
+                            .global::<Logic>()
+                            .get_day_columns()
+                            .iter()
+                            .collect::<Vec<_>>();
+                        let index = index as usize;
+                        if index > 0 && index < columns.len() {
+                            columns.swap(index - 1, index);
+                        }
+
+                        ui.global::<Logic>()
+                            .set_day_columns(ModelRc::new(VecModel::from(columns)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_day_column_push_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_day_column_push_clicked(move || {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut columns = ui
+                            .global::<Logic>()
+                            .get_day_columns()
+                            .iter()
+                            .collect::<Vec<_>>();
+                        columns.push(DayColumnConfig {
+                            field: DayField::Status.name().into(),
+                            ..Default::default()
+                        });
+
+                        ui.global::<Logic>()
+                            .set_day_columns(ModelRc::new(VecModel::from(columns)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_calendar_import_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui
+            .global::<Logic>()
+            .on_calendar_import_clicked(move || {
+                let ui_weak = ui_weak.clone();
+                let db = db.clone();
+                task::spawn(async move {
+                    let mut res = Ok(());
+                    if let Some(file) =
+                        select_file("请选择节假日安排", &["xls", "xlsx", "csv"]).await
+                    {
+                        // 导入节假日和调休上班日，与已有日历合并
+                        res = calendar::import(file, &db).and_then(|summary| {
+                            show_calendar(ui_weak.clone(), calendar::load(&db)?);
+                            show_info(ui_weak.clone(), summary.to_string());
+                            Ok(())
+                        });
+                    }
+                    reset_button(ui_weak, res);
+                });
+            });
+    }
+
+    fn on_calendar_save_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_calendar_save_clicked(move || {
+            let ui_weak = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let mut res = Ok(());
+                if let Some(mut days) = get_calendar(ui_weak.clone()).await {
+                    days.sort_by_key(|(date, _)| *date);
+                    res = calendar::save(&db, &days).map(|_| {
+                        show_info(
+                            ui_weak.clone(),
+                            format!("工作日历已保存，共{}个节假日或调休上班日", days.len()),
+                        );
+                        show_calendar(ui_weak.clone(), days);
+                    });
+                }
+                reset_button(ui_weak, res);
+            });
+        });
+    }
+
+    fn on_calendar_remove_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_calendar_remove_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut days = ui
+                            .global::<Logic>()
+                            .get_calendar_days()
+                            .iter()
+                            .collect::<Vec<CalendarDay>>();
+                        days.remove(index as usize);
+
+                        ui.global::<Logic>()
+                            .set_calendar_days(ModelRc::new(VecModel::from(days)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_calendar_push_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui.global::<Logic>().on_calendar_push_clicked(move || {
+            ui_weak
+                .upgrade_in_event_loop(move |ui| {
+                    let mut days = ui
+                        .global::<Logic>()
+                        .get_calendar_days()
+                        .iter()
+                        .collect::<Vec<CalendarDay>>();
+                    days.push(CalendarDay::default());
+
+                    ui.global::<Logic>()
+                        .set_calendar_days(ModelRc::new(VecModel::from(days)));
+                    ui.global::<Logic>().set_button_enabled(true);
+                })
+                .ok();
+        });
+    }
+
+    fn on_keyword_save_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_keyword_save_clicked(move || {
+            let ui_weak = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let mut res = Ok(());
+                if let Some(mut rules) = get_keyword_rules(ui_weak.clone()).await {
+                    rules.sort_by_key(|rule| rule.priority);
+                    res = keyword::save(&db, &rules).map(|_| {
+                        show_info(
+                            ui_weak.clone(),
+                            format!("关键词规则已保存，共{}条", rules.len()),
+                        );
+                        show_keyword_rules(ui_weak.clone(), rules);
+                    });
+                }
+                reset_button(ui_weak, res);
+            });
+        });
+    }
+
+    fn on_keyword_reset_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui.global::<Logic>().on_keyword_reset_clicked(move || {
+            ui_weak
+                .upgrade_in_event_loop(move |ui| {
+                    // 只恢复界面上的列表，保存后才生效
+                    set_keyword_rules(&ui, &keyword::default_rules());
+                    ui.global::<Logic>().set_button_enabled(true);
+                })
+                .ok();
+        });
+    }
+
+    fn on_keyword_remove_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_keyword_remove_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut rules = ui
+                            .global::<Logic>()
+                            .get_keyword_rules()
+                            .iter()
+                            .collect::<Vec<KeywordRuleConfig>>();
+                        rules.remove(index as usize);
+
+                        ui.global::<Logic>()
+                            .set_keyword_rules(ModelRc::new(VecModel::from(rules)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_keyword_push_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui.global::<Logic>().on_keyword_push_clicked(move || {
+            ui_weak
+                .upgrade_in_event_loop(move |ui| {
+                    let mut rules = ui
+                        .global::<Logic>()
+                        .get_keyword_rules()
+                        .iter()
+                        .collect::<Vec<KeywordRuleConfig>>();
+                    let priority = rules.len() + 1;
+                    rules.push(KeywordRuleConfig {
+                        priority: priority.to_string().into(),
+                        ..Default::default()
+                    });
+
+                    ui.global::<Logic>()
+                        .set_keyword_rules(ModelRc::new(VecModel::from(rules)));
+                    ui.global::<Logic>().set_button_enabled(true);
+                })
+                .ok();
+        });
+    }
+
+    fn on_highlight_save_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui
+            .global::<Logic>()
+            .on_highlight_save_clicked(move || {
+                let ui_weak = ui_weak.clone();
+                let db = db.clone();
+                task::spawn(async move {
+                    let mut res = Ok(());
+                    if let Some(rules) = get_highlight_rules(ui_weak.clone()).await {
+                        res = highlight::save(&db, &rules).map(|_| {
+                            show_info(
+                                ui_weak.clone(),
+                                format!("高亮规则已保存，共{}条", rules.len()),
+                            );
+                            show_highlight_rules(ui_weak.clone(), rules);
+                        });
+                    }
+                    reset_button(ui_weak, res);
+                });
+            });
+    }
+
+    fn on_highlight_reset_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_highlight_reset_clicked(move || {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        // 只恢复界面上的列表，保存后才生效
+                        set_highlight_rules(&ui, &highlight::default_rules());
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_highlight_remove_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_highlight_remove_clicked(move |index| {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut rules = ui
+                            .global::<Logic>()
+                            .get_highlight_rules()
+                            .iter()
+                            .collect::<Vec<HighlightRuleConfig>>();
+                        rules.remove(index as usize);
+
+                        ui.global::<Logic>()
+                            .set_highlight_rules(ModelRc::new(VecModel::from(rules)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_highlight_push_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        self.ui
+            .global::<Logic>()
+            .on_highlight_push_clicked(move || {
+                ui_weak
+                    .upgrade_in_event_loop(move |ui| {
+                        let mut rules = ui
+                            .global::<Logic>()
+                            .get_highlight_rules()
+                            .iter()
+                            .collect::<Vec<HighlightRuleConfig>>();
+                        rules.push(HighlightRuleConfig {
+                            hours_below: "0".into(),
+                            ..Default::default()
+                        });
+
+                        ui.global::<Logic>()
+                            .set_highlight_rules(ModelRc::new(VecModel::from(rules)));
+                        ui.global::<Logic>().set_button_enabled(true);
+                    })
+                    .ok();
+            });
+    }
+
+    fn on_execute_clicked(&self) {
+        let ui_weak = self.ui.as_weak();
+        let db = self.db.clone();
+        self.ui.global::<Logic>().on_home_execute_clicked(move || {
+            let ui_weak1 = ui_weak.clone();
+            let ui_weak2 = ui_weak.clone();
+            let db = db.clone();
+            task::spawn(async move {
+                let mut res = Ok(());
+                if let Some(user_input) = get_input(ui_weak1).await {
+                    if let Some(file) = select_file("请选择模板", &["xlsx"]).await {
+                        // 另存为新文件，选择模板本身时覆盖模板并自动备份
+                        let output = match default_output_name(&user_input) {
+                            Ok(name) => save_file("报表另存为", &file, &name).await,
+                            Err(e) => {
+                                res = Err(e);
+                                None
+                            }
+                        };
+                        if let Some(output) = output {
+                            // 保存输入，并根据sled信息生成结果
+                            res = generate_report(file, output, &user_input, &db).map(|summary| {
+                                show_info(ui_weak2.clone(), summary.to_string());
+                            });
+                        }
+                    }
+                }
+                reset_button(ui_weak2, res);
+            });
+        });
+    }
+}
+
+/// sheet名称为空时界面显示的选项
+const SHEET_BY_ORDER: &str = "按顺序";
+
+fn sheet_name_str(sheet_name: &str) -> &str {
+    if sheet_name.is_empty() {
+        SHEET_BY_ORDER
+    } else {
+        sheet_name
+    }
+}
+
+/// 设置sheet名称下拉框的选项，已选的名称不在其中时也保留
+fn set_template_sheet_names(ui: &Ui, names: Vec<String>) {
+    let mut options = vec![SharedString::from(SHEET_BY_ORDER)];
+    let configs = ui.global::<Logic>().get_template_configs();
+    let selected = configs.iter().map(|cfg| cfg.template_sheet_name);
+    for name in names.into_iter().map(SharedString::from).chain(selected) {
+        if !options.contains(&name) {
+            options.push(name);
+        }
+    }
+    ui.global::<Logic>()
+        .set_template_sheet_names(ModelRc::new(VecModel::from(options)));
+}
+
+/// 界面上显示的每日列，表头中的换行显示为“\n”
+fn day_column_configs(columns: &[DayColumn]) -> ModelRc<DayColumnConfig> {
+    let columns = columns
+        .iter()
+        .map(|column| DayColumnConfig {
+            field: column.field.name().into(),
+            header: column.header.replace('\n', "\\n").into(),
+            width: column.width.to_string().into(),
+        })
+        .collect::<Vec<_>>();
+    ModelRc::new(VecModel::from(columns))
+}
+
+/// 把正在编辑的每日列存回对应的模板配置
+fn store_day_columns(ui: &Ui) {
+    let Ok(index) = usize::try_from(ui.global::<Logic>().get_day_columns_index()) else {
+        return;
+    };
+    let configs = ui.global::<Logic>().get_template_configs();
+    if let Some(mut cfg) = configs.row_data(index) {
+        let columns = ui
+            .global::<Logic>()
+            .get_day_columns()
+            .iter()
+            .collect::<Vec<_>>();
+        cfg.day_columns = ModelRc::new(VecModel::from(columns));
+        configs.set_row_data(index, cfg);
+    }
+}
+
+/// 解析界面上一个模板配置的每日列，`index`为界面上显示的序号
+fn parse_day_column_configs(
+    index: usize,
+    columns: &ModelRc<DayColumnConfig>,
+) -> Result<Vec<DayColumn>> {
+    let mut day_columns = Vec::new();
+    for column in columns.iter() {
+        let field = DayField::from_name(&column.field).ok_or(anyhow!(
+            "序号{index}的每日列，内容填写有误：{}",
+            column.field
+        ))?;
+        let header = match column.header.trim() {
+            "" => field.default_header().to_string(),
+            header => header.replace("\\n", "\n"),
+        };
+        render_header(&header, Date::MIN).map_err(|e| anyhow!("序号{index}的每日列，{e}"))?;
+        let width = match column.width.trim() {
+            "" => 0.0,
+            width => width
+                .parse::<f64>()
+                .ok()
+                .filter(|width| *width >= 0.0)
+                .ok_or(anyhow!("序号{index}的每日列，列宽填写有误：{width}"))?,
+        };
+        day_columns.push(DayColumn {
+            field,
+            header,
+            width,
+        });
+    }
+    if day_columns.is_empty() {
+        return Err(anyhow!("序号{index}的模板配置至少需要一列每日列"));
+    }
+    Ok(day_columns)
+}
+
+/// 解析界面上一个模板配置的工时设置，`index`为界面上显示的序号
+fn parse_work_time(index: usize, cfg: &TemplateConfig) -> Result<WorkTime> {
+    let default = WorkTime::default();
+    let number = |text: &SharedString, default: f64, name: &str| match text.trim() {
+        "" => Ok(default),
+        text => text
+            .parse::<f64>()
+            .map_err(|_| anyhow!("序号{index}的{name}，填写有误：{text}")),
+    };
+    let work_time = WorkTime {
+        unit: WorkTimeUnit::from_name(&cfg.work_time_unit).unwrap_or(default.unit),
+        rounding: Rounding::from_name(&cfg.rounding).unwrap_or(default.rounding),
+        precision: number(&cfg.precision, default.precision, "工时精度")?,
+        standard_hours: number(&cfg.standard_hours, default.standard_hours, "每天标准工时")?,
+    };
+    work_time
+        .check()
+        .map_err(|e| anyhow!("序号{index}的模板配置，{e}"))?;
+    Ok(work_time)
+}
+
+fn parse_hours(text: &str) -> Option<f64> {
+    text.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|hours| (0.0..=24.0).contains(hours))
+}
+
+fn long_date_string(date: i32) -> SharedString {
+    let format = format_description!("[year]-[month]-[day]");
+    SharedString::from(
+        Date::from_julian_day(date)
+            .unwrap()
+            .format(&format)
+            .unwrap(),
+    )
+}
+
+async fn select_file(title: &str, extensions: &[&str]) -> Option<PathBuf> {
+    AsyncFileDialog::new()
+        .add_filter("excel", extensions)
+        .set_title(title)
+        .pick_file()
+        .await
+        .map(|file| file.path().to_owned())
+}
+
+async fn save_file(title: &str, template: &Path, file_name: &str) -> Option<PathBuf> {
+    let mut dialog = AsyncFileDialog::new()
+        .add_filter("excel", &["xlsx"])
+        .set_title(title)
+        .set_file_name(file_name);
+    if let Some(dir) = template.parent() {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.save_file().await.map(|file| file.path().to_owned())
+}
+
+async fn get_input(ui_weak: slint::Weak<Ui>) -> Option<UserInput> {
+    let (s, r) = channel::bounded(1);
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            if let Err(e) = parse_input(&ui, &s) {
+                ui.set_alert_text(SharedString::from(e.to_string()));
+                ui.invoke_alert();
+            }
+            s.close();
+        })
+        .unwrap();
+
+    r.recv().await.ok()
+}
+
+/// 列号显示为字母，导入时按表头名称重新定位后也用它回填
+fn set_input_cols(ui: &Ui, user_input: &UserInput) {
+    ui.global::<Logic>().set_statistics_employee_id_col(
+        string_from_column_index(&user_input.statistics_employee_id_col).into(),
+    );
+    ui.global::<Logic>()
+        .set_statistics_date_col(string_from_column_index(&user_input.statistics_date_col).into());
+    ui.global::<Logic>().set_statistics_enter_result_col(
+        string_from_column_index(&user_input.statistics_enter_result_col).into(),
+    );
+    ui.global::<Logic>().set_statistics_leave_result_col(
+        string_from_column_index(&user_input.statistics_leave_result_col).into(),
+    );
+    ui.global::<Logic>().set_statistics_work_minutes_col(
+        string_from_column_index(&user_input.statistics_work_minutes_col).into(),
+    );
+    ui.global::<Logic>().set_record_employee_id_col(
+        string_from_column_index(&user_input.record_employee_id_col).into(),
+    );
+    ui.global::<Logic>()
+        .set_record_date_col(string_from_column_index(&user_input.record_date_col).into());
+    ui.global::<Logic>().set_record_abnormal_reason_col(
+        string_from_column_index(&user_input.record_abnormal_reason_col).into(),
+    );
+    ui.global::<Logic>().set_punch_employee_id_col(
+        string_from_column_index(&user_input.punch_employee_id_col).into(),
+    );
+    ui.global::<Logic>()
+        .set_punch_time_col(string_from_column_index(&user_input.punch_time_col).into());
+    ui.global::<Logic>()
+        .set_punch_device_col(string_from_column_index(&user_input.punch_device_col).into());
+}
+
+fn show_input_cols(ui_weak: slint::Weak<Ui>, user_input: UserInput) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| set_input_cols(&ui, &user_input))
+        .ok();
+}
+
+fn get_header(value: SharedString) -> String {
+    value.trim().to_string()
+}
+
+fn parse_input(ui: &Ui, sender: &Sender<UserInput>) -> Result<()> {
+    let format = format_description!("[year]-[month]-[day]");
+
+    let mut start_date_str = ui.global::<Logic>().get_start_date();
+    let opt_start_date =
+        Date::parse(&start_date_str, &format).map_err(|_| anyhow!("开始日期，填写有误，请检查"))?;
+
+    let mut end_date_str = ui.global::<Logic>().get_end_date();
+    let opt_end_date =
+        Date::parse(&end_date_str, &format).map_err(|_| anyhow!("结束日期，填写有误，请检查"))?;
+
+    let statistics_employee_id_col = parse_input_col!(
+        ui,
+        get_statistics_employee_id_col,
+        set_statistics_employee_id_col
+    )
+    .ok_or(anyhow!("每日统计表-工号，填写有误，请检查"))?;
+    let statistics_date_col =
+        parse_input_col!(ui, get_statistics_date_col, set_statistics_date_col)
+            .ok_or(anyhow!("每日统计表-日期，填写有误，请检查"))?;
+    let statistics_enter_result_col = parse_input_col!(
+        ui,
+        get_statistics_enter_result_col,
+        set_statistics_enter_result_col
+    )
+    .ok_or(anyhow!("每日统计表-上班-打卡结果1，填写有误，请检查"))?;
+    let statistics_leave_result_col = parse_input_col!(
+        ui,
+        get_statistics_leave_result_col,
+        set_statistics_leave_result_col
+    )
+    .ok_or(anyhow!("每日统计表-下班-打卡结果1，填写有误，请检查"))?;
+    let statistics_work_minutes_col = parse_input_col!(
+        ui,
+        get_statistics_work_minutes_col,
+        set_statistics_work_minutes_col
+    )
+    .ok_or(anyhow!("每日统计表-工作时长(分钟)，填写有误，请检查"))?;
+    let statistics_start_row =
+        parse_input_row!(ui, get_statistics_start_row, set_statistics_start_row)
+            .ok_or(anyhow!("每日统计表，数据起始行号，填写有误，请检查"))?;
+    let statistics_header_row =
+        parse_input_row!(ui, get_statistics_header_row, set_statistics_header_row)
+            .ok_or(anyhow!("每日统计表，表头行号，填写有误，请检查"))?;
+    let record_employee_id_col =
+        parse_input_col!(ui, get_record_employee_id_col, set_record_employee_id_col)
+            .ok_or(anyhow!("原始记录表-工号，填写有误，请检查"))?;
+    let record_date_col = parse_input_col!(ui, get_record_date_col, set_record_date_col)
+        .ok_or(anyhow!("原始记录表-日期，填写有误，请检查"))?;
+    let record_abnormal_reason_col = parse_input_col!(
+        ui,
+        get_record_abnormal_reason_col,
+        set_record_abnormal_reason_col
+    )
+    .ok_or(anyhow!("原始记录表-异常打卡原因，填写有误，请检查"))?;
+    let record_start_row = parse_input_row!(ui, get_record_start_row, set_record_start_row)
+        .ok_or(anyhow!("原始记录表，数据起始行号，填写有误，请检查"))?;
+    let record_header_row = parse_input_row!(ui, get_record_header_row, set_record_header_row)
+        .ok_or(anyhow!("原始记录表，表头行号，填写有误，请检查"))?;
+    let punch_employee_id_col =
+        parse_input_col!(ui, get_punch_employee_id_col, set_punch_employee_id_col)
+            .ok_or(anyhow!("打卡记录-工号，填写有误，请检查"))?;
+    let punch_time_col = parse_input_col!(ui, get_punch_time_col, set_punch_time_col)
+        .ok_or(anyhow!("打卡记录-打卡时间，填写有误，请检查"))?;
+    let punch_device_col = parse_input_col!(ui, get_punch_device_col, set_punch_device_col)
+        .ok_or(anyhow!("打卡记录-打卡设备/地点，填写有误，请检查"))?;
+    let punch_start_row = parse_input_row!(ui, get_punch_start_row, set_punch_start_row)
+        .ok_or(anyhow!("打卡记录，数据起始行号，填写有误，请检查"))?;
+    let punch_header_row = parse_input_row!(ui, get_punch_header_row, set_punch_header_row)
+        .ok_or(anyhow!("打卡记录，表头行号，填写有误，请检查"))?;
+    let shift_time = |text: SharedString, name: &str| {
+        parse_minutes(&text).ok_or(anyhow!("{name}，填写有误，请填写如 9:00 的时间"))
+    };
+    let shift = Shift {
+        start: shift_time(ui.global::<Logic>().get_shift_start(), "上班时间")?,
+        end: shift_time(ui.global::<Logic>().get_shift_end(), "下班时间")?,
+        lunch_start: shift_time(ui.global::<Logic>().get_lunch_start(), "午休开始")?,
+        lunch_end: shift_time(ui.global::<Logic>().get_lunch_end(), "午休结束")?,
+    };
+
+    store_day_columns(ui);
+    let details = ui
+        .global::<Logic>()
+        .get_template_configs()
+        .iter()
+        .enumerate()
+        .map(|(i, cfg)| {
+            Ok((
+                parse_day_column_configs(i, &cfg.day_columns)?,
+                parse_work_time(i, &cfg)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut changed = false;
+    let (mut template_cfg, template_cfg_str) = ui
+        .global::<Logic>()
+        .get_template_configs()
+        .iter()
+        .enumerate()
+        .fold(
+            (Vec::new(), Vec::new()),
+            |(mut cfg, mut cfg_str), (i, v)| {
+                let default_employee_id_col = if i == 0 { 1 } else { 4 };
+                let default_start_col = if i == 0 { 7 } else { 9 };
+                let default_title_row = 2;
+
+                let old_value = v.clone();
+                let parsed_value = SheetConfig {
+                    sheet_name: match v.template_sheet_name.as_str() {
+                        "" | SHEET_BY_ORDER => String::new(),
+                        name => name.trim().to_string(),
+                    },
+                    ..SheetConfig::new(
+                        parse_col(&v.template_employee_id_col).unwrap_or(default_employee_id_col),
+                        parse_col(&v.template_start_col).unwrap_or(default_start_col),
+                        v.template_title_row
+                            .parse::<u32>()
+                            .unwrap_or(default_title_row),
+                    )
+                };
+                let parsed_value_str = TemplateConfig {
+                    template_employee_id_col: string_from_column_index(
+                        &parsed_value.employee_id_col,
+                    )
+                    .into(),
+                    template_start_col: string_from_column_index(&parsed_value.start_col).into(),
+                    template_title_row: parsed_value.title_row.to_string().into(),
+                    template_sheet_name: sheet_name_str(&parsed_value.sheet_name).into(),
+                    ..v.clone()
+                };
+
+                changed |= old_value.ne(&parsed_value_str);
+
+                cfg.push(parsed_value);
+                cfg_str.push(parsed_value_str);
+
+                (cfg, cfg_str)
+            },
+        );
+
+    if changed {
+        ui.global::<Logic>()
+            .set_template_configs(ModelRc::new(VecModel::from(template_cfg_str)));
+    }
+    for (cfg, (day_columns, work_time)) in template_cfg.iter_mut().zip(details) {
+        cfg.day_columns = day_columns;
+        cfg.work_time = work_time;
+    }
+
+    let mut user_input = UserInput {
+        start_date: opt_start_date.to_julian_day(),
+        end_date: opt_end_date.to_julian_day(),
+        statistics_employee_id_col,
+        statistics_date_col,
+        statistics_enter_result_col,
+        statistics_leave_result_col,
+        statistics_work_minutes_col,
+        statistics_start_row,
+        statistics_header_row,
+        statistics_employee_id_header: get_header(
+            ui.global::<Logic>().get_statistics_employee_id_header(),
+        ),
+        statistics_date_header: get_header(ui.global::<Logic>().get_statistics_date_header()),
+        statistics_enter_result_header: get_header(
+            ui.global::<Logic>().get_statistics_enter_result_header(),
+        ),
+        statistics_leave_result_header: get_header(
+            ui.global::<Logic>().get_statistics_leave_result_header(),
+        ),
+        statistics_work_minutes_header: get_header(
+            ui.global::<Logic>().get_statistics_work_minutes_header(),
+        ),
+        record_employee_id_col,
+        record_date_col,
+        record_abnormal_reason_col,
+        record_start_row,
+        record_header_row,
+        record_employee_id_header: get_header(ui.global::<Logic>().get_record_employee_id_header()),
+        record_date_header: get_header(ui.global::<Logic>().get_record_date_header()),
+        record_abnormal_reason_header: get_header(
+            ui.global::<Logic>().get_record_abnormal_reason_header(),
+        ),
+        template_cfg,
+        summary_columns: ui.global::<Logic>().get_summary_columns(),
+        style_preset: StylePreset::from_name(&ui.global::<Logic>().get_style_preset())
+            .unwrap_or_default(),
+        clear_block: ui.global::<Logic>().get_clear_block(),
+        no_data_text: ui.global::<Logic>().get_no_data_text().trim().to_string(),
+        overtime: OvertimeRule {
+            enabled: ui.global::<Logic>().get_overtime_enabled(),
+            workday_hours: parse_hours(&ui.global::<Logic>().get_overtime_workday_hours())
+                .ok_or(anyhow!("工作日标准工时，填写有误，请填写0到24之间的小时数"))?,
+            restday_hours: parse_hours(&ui.global::<Logic>().get_overtime_restday_hours())
+                .ok_or(anyhow!("休息日标准工时，填写有误，请填写0到24之间的小时数"))?,
+        },
+        punch_employee_id_col,
+        punch_time_col,
+        punch_device_col,
+        punch_start_row,
+        punch_header_row,
+        punch_employee_id_header: get_header(ui.global::<Logic>().get_punch_employee_id_header()),
+        punch_time_header: get_header(ui.global::<Logic>().get_punch_time_header()),
+        punch_device_header: get_header(ui.global::<Logic>().get_punch_device_header()),
+        shift,
+    };
+
+    if start_date_str > end_date_str {
+        std::mem::swap(&mut start_date_str, &mut end_date_str);
+        std::mem::swap(&mut user_input.start_date, &mut user_input.end_date);
+        ui.global::<Logic>().set_start_date(start_date_str);
+        ui.global::<Logic>().set_end_date(end_date_str);
+    }
+
+    sender.send_blocking(user_input)?;
+
+    Ok(())
+}
+
+fn set_calendar(ui: &Ui, days: &[(Date, SpecialDay)]) {
+    let days = days
+        .iter()
+        .map(|(date, day)| CalendarDay {
+            date: date.to_string().into(),
+            workday: day.workday,
+            name: day.name.as_str().into(),
+        })
+        .collect::<Vec<_>>();
+    ui.global::<Logic>()
+        .set_calendar_days(ModelRc::new(VecModel::from(days)));
+}
+
+fn show_calendar(ui_weak: slint::Weak<Ui>, days: Vec<(Date, SpecialDay)>) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| set_calendar(&ui, &days))
+        .ok();
+}
+
+async fn get_calendar(ui_weak: slint::Weak<Ui>) -> Option<Vec<(Date, SpecialDay)>> {
+    let (s, r) = channel::bounded(1);
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            if let Err(e) = parse_calendar(&ui, &s) {
+                ui.set_alert_text(SharedString::from(e.to_string()));
+                ui.invoke_alert();
+            }
+            s.close();
+        })
+        .unwrap();
+
+    r.recv().await.ok()
+}
+
+fn parse_calendar(ui: &Ui, sender: &Sender<Vec<(Date, SpecialDay)>>) -> Result<()> {
+    let format = format_description!("[year]-[month]-[day]");
+    let mut days = Vec::new();
+    for (i, day) in ui.global::<Logic>().get_calendar_days().iter().enumerate() {
+        let date = Date::parse(day.date.trim(), &format)
+            .map_err(|_| anyhow!("工作日历第{}行，日期填写有误，请检查", i + 1))?;
+        if days.iter().any(|(other, _)| *other == date) {
+            return Err(anyhow!("工作日历第{}行，日期{date}重复，请检查", i + 1));
+        }
+        days.push((
+            date,
+            SpecialDay {
+                workday: day.workday,
+                name: day.name.trim().to_string(),
+            },
+        ));
+    }
+
+    sender.send_blocking(days)?;
+
+    Ok(())
+}
+
+fn set_keyword_rules(ui: &Ui, rules: &[KeywordRule]) {
+    let rules = rules
+        .iter()
+        .map(|rule| KeywordRuleConfig {
+            pattern: rule.pattern.as_str().into(),
+            priority: rule.priority.to_string().into(),
+            enter_text: rule.enter_text.as_str().into(),
+            leave_text: rule.leave_text.as_str().into(),
+            reason_text: rule.reason_text.as_str().into(),
+        })
+        .collect::<Vec<_>>();
+    ui.global::<Logic>()
+        .set_keyword_rules(ModelRc::new(VecModel::from(rules)));
+}
+
+fn show_keyword_rules(ui_weak: slint::Weak<Ui>, rules: Vec<KeywordRule>) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| set_keyword_rules(&ui, &rules))
+        .ok();
+}
+
+async fn get_keyword_rules(ui_weak: slint::Weak<Ui>) -> Option<Vec<KeywordRule>> {
+    let (s, r) = channel::bounded(1);
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            if let Err(e) = parse_keyword_rules(&ui, &s) {
+                ui.set_alert_text(SharedString::from(e.to_string()));
+                ui.invoke_alert();
+            }
+            s.close();
+        })
+        .unwrap();
+
+    r.recv().await.ok()
+}
+
+fn parse_keyword_rules(ui: &Ui, sender: &Sender<Vec<KeywordRule>>) -> Result<()> {
+    let mut rules = Vec::new();
+    for (i, rule) in ui.global::<Logic>().get_keyword_rules().iter().enumerate() {
+        if rule.pattern.trim().is_empty() {
+            return Err(anyhow!("关键词规则第{}行，关键词不能为空，请检查", i + 1));
+        }
+        let priority = rule
+            .priority
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow!("关键词规则第{}行，优先级填写有误，请检查", i + 1))?;
+        rules.push(KeywordRule {
+            pattern: rule.pattern.trim().to_string(),
+            priority,
+            enter_text: rule.enter_text.trim().to_string(),
+            leave_text: rule.leave_text.trim().to_string(),
+            reason_text: rule.reason_text.trim().to_string(),
+        });
+    }
+
+    sender.send_blocking(rules)?;
+
+    Ok(())
+}
+
+fn set_highlight_rules(ui: &Ui, rules: &[HighlightRule]) {
+    let rules = rules
+        .iter()
+        .map(|rule| HighlightRuleConfig {
+            pattern: rule.pattern.as_str().into(),
+            hours_below: rule.hours_below.to_string().into(),
+            fill_color: rule.fill_color.as_str().into(),
+            font_color: rule.font_color.as_str().into(),
+        })
+        .collect::<Vec<_>>();
+    ui.global::<Logic>()
+        .set_highlight_rules(ModelRc::new(VecModel::from(rules)));
+}
+
+fn show_highlight_rules(ui_weak: slint::Weak<Ui>, rules: Vec<HighlightRule>) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| set_highlight_rules(&ui, &rules))
+        .ok();
+}
+
+async fn get_highlight_rules(ui_weak: slint::Weak<Ui>) -> Option<Vec<HighlightRule>> {
+    let (s, r) = channel::bounded(1);
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            if let Err(e) = parse_highlight_rules(&ui, &s) {
+                ui.set_alert_text(SharedString::from(e.to_string()));
+                ui.invoke_alert();
+            }
+            s.close();
+        })
+        .unwrap();
+
+    r.recv().await.ok()
+}
+
+fn parse_highlight_rules(ui: &Ui, sender: &Sender<Vec<HighlightRule>>) -> Result<()> {
+    let mut rules = Vec::new();
+    for (i, rule) in ui
+        .global::<Logic>()
+        .get_highlight_rules()
+        .iter()
+        .enumerate()
+    {
+        let hours_below = match rule.hours_below.trim() {
+            "" => 0.0,
+            hours => hours
+                .parse::<f64>()
+                .ok()
+                .filter(|hours| *hours >= 0.0)
+                .ok_or(anyhow!("高亮规则第{}行，工时填写有误，请检查", i + 1))?,
+        };
+        if rule.pattern.trim().is_empty() && hours_below == 0.0 {
+            return Err(anyhow!(
+                "高亮规则第{}行，关键词和工时至少填写一项，请检查",
+                i + 1
+            ));
+        }
+        let color = |text: &str| {
+            highlight::parse_color(text).map_err(|e| anyhow!("高亮规则第{}行，{e}", i + 1))
+        };
+        rules.push(HighlightRule {
+            pattern: rule.pattern.trim().to_string(),
+            hours_below,
+            fill_color: color(&rule.fill_color)?,
+            font_color: color(&rule.font_color)?,
+        });
+    }
+
+    sender.send_blocking(rules)?;
+
+    Ok(())
+}
+
+fn show_info(ui_weak: slint::Weak<Ui>, text: String) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            ui.set_info_text(SharedString::from(text));
+            ui.invoke_info();
+        })
+        .ok();
+}
+
+fn reset_button(ui_weak: slint::Weak<Ui>, res: Result<()>) {
+    ui_weak
+        .upgrade_in_event_loop(move |ui| {
+            if let Err(e) = res {
+                ui.set_alert_text(SharedString::from(e.to_string()));
+                ui.invoke_alert();
+            }
+            ui.global::<Logic>().set_button_enabled(true);
+        })
+        .ok();
+}
+
+/// 界面还没起来时（如数据库打不开）用系统对话框提示错误
+pub(crate) fn show_error(e: &anyhow::Error) {
+    MessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title("考勤助手")
+        .set_description(e.to_string())
+        .show();
+}
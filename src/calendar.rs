@@ -0,0 +1,246 @@
+//! 工作日历：默认周一至周五上班、周末休息，
+//! 法定节假日和调休上班日作为例外存在sled的`calendar` tree中，以“{日期}”为键。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use sled::{Batch, Db};
+use speedy::{Readable, Writable};
+use time::{macros::format_description, Date, Duration, Weekday};
+
+use crate::import::ImportSummary;
+use crate::table::Table;
+
+const CALENDAR_TREE: &str = "calendar";
+
+/// 与默认安排不同的一天
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct SpecialDay {
+    /// true为调休上班，false为放假
+    pub workday: bool,
+    /// 节日名称等备注
+    pub name: String,
+}
+
+/// 某天是否上班
+pub fn is_workday(db: &Db, date: Date) -> Result<bool> {
+    let calendar = db.open_tree(CALENDAR_TREE)?;
+    Ok(match calendar.get(date.to_string())? {
+        Some(value) => SpecialDay::read_from_buffer(&value)?.workday,
+        None => !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday),
+    })
+}
+
+/// 两个日期之间（含首尾）所有上班的日子
+pub fn workdays(db: &Db, start: Date, end: Date) -> Result<Vec<Date>> {
+    let mut days = Vec::new();
+    let mut date = start;
+    while date <= end {
+        if is_workday(db, date)? {
+            days.push(date);
+        }
+        date = date.saturating_add(Duration::days(1));
+    }
+    Ok(days)
+}
+
+/// 全部例外日期，按日期排序
+pub fn load(db: &Db) -> Result<Vec<(Date, SpecialDay)>> {
+    let calendar = db.open_tree(CALENDAR_TREE)?;
+    let mut days = Vec::new();
+    for kv in calendar.iter() {
+        let (key, value) = kv?;
+        let date = Date::parse(
+            &String::from_utf8_lossy(&key),
+            format_description!("[year]-[month]-[day]"),
+        )?;
+        days.push((date, SpecialDay::read_from_buffer(&value)?));
+    }
+    Ok(days)
+}
+
+/// 用界面编辑后的例外日期替换全部日历
+pub fn save(db: &Db, days: &[(Date, SpecialDay)]) -> Result<()> {
+    let calendar = db.open_tree(CALENDAR_TREE)?;
+    let mut batch = Batch::default();
+    for key in calendar.iter().keys() {
+        batch.remove(key?);
+    }
+    for (date, day) in days {
+        batch.insert(date.to_string().as_bytes(), day.write_to_vec()?);
+    }
+    calendar.apply_batch(batch)?;
+    Ok(())
+}
+
+/// 从xlsx、xls或csv文件导入节假日安排，每行为“日期，类型，名称（可选）”，
+/// 类型填“休”“放假”等为放假，“班”“上班”“调休”等为调休上班；已有的同一天会被覆盖
+///
+/// 第一行日期无法识别时当作表头跳过
+pub fn import(path: impl AsRef<Path>, db: &Db) -> Result<ImportSummary> {
+    let worksheet = Table::read(path)?;
+    let calendar = db.open_tree(CALENDAR_TREE)?;
+    let mut summary = ImportSummary::default();
+    let mut days = BTreeMap::new();
+
+    for r in 1..worksheet.get_highest_row() + 1 {
+        if worksheet.is_row_empty(r) {
+            continue;
+        }
+        let Some(date) = worksheet.get_date((1, r)) else {
+            if r > 1 {
+                summary.rows_read += 1;
+                let text = worksheet.get_formatted_value((1, r));
+                summary.skip(r, format!("日期无法识别：{text}"));
+            }
+            continue;
+        };
+        summary.rows_read += 1;
+
+        let kind = worksheet.get_formatted_value((2, r));
+        let Some(workday) = parse_kind(&kind) else {
+            summary.skip(r, format!("类型无法识别：{kind}，请填写“休”或“班”"));
+            continue;
+        };
+        let day = SpecialDay {
+            workday,
+            name: worksheet.get_formatted_value((3, r)).trim().to_string(),
+        };
+        let key = date.to_string();
+        summary.imported(
+            date,
+            !days.contains_key(&key) && !calendar.contains_key(&key)?,
+        );
+        days.insert(key, day);
+    }
+
+    let mut batch = Batch::default();
+    for (key, day) in days {
+        batch.insert(key.as_bytes(), day.write_to_vec()?);
+    }
+    calendar.apply_batch(batch)?;
+    Ok(summary)
+}
+
+/// 放假为Some(false)，调休上班为Some(true)
+fn parse_kind(kind: &str) -> Option<bool> {
+    let kind = kind.trim();
+    if kind.contains('班') || kind.contains("工作") || kind == "调休" {
+        Some(true)
+    } else if kind.contains('休') || kind.contains('假') {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use time::macros::date;
+
+    use super::*;
+
+    fn temporary_db() -> Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    #[test]
+    fn kind_classification() {
+        for kind in ["班", "上班", "补班", "调休", "工作日", " 调休上班 "] {
+            assert_eq!(parse_kind(kind), Some(true), "{kind}");
+        }
+        for kind in ["休", "休息", "放假", "假期", "调休放假"] {
+            assert_eq!(parse_kind(kind), Some(false), "{kind}");
+        }
+        for kind in ["", "节日", "？"] {
+            assert_eq!(parse_kind(kind), None, "{kind}");
+        }
+    }
+
+    #[test]
+    fn import_skips_header_row() {
+        let db = temporary_db();
+        let path = env::temp_dir().join(format!("liando_{}_calendar.csv", process::id()));
+        fs::write(
+            &path,
+            "日期,类型,名称\n2023-04-23,补班,劳动节调休\n2023-05-01,休息,劳动节\n日期,类型\n2023-05-02,？\n",
+        )
+        .unwrap();
+        let summary = import(&path, &db).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!((summary.rows_read, summary.created), (4, 2));
+        // 只有第一行当作表头，其他无法识别的行都列出来
+        let skipped = summary
+            .skipped
+            .iter()
+            .map(|skipped| skipped.row)
+            .collect::<Vec<_>>();
+        assert_eq!(skipped, vec![4, 5]);
+        assert_eq!(
+            load(&db).unwrap(),
+            vec![
+                (
+                    date!(2023 - 04 - 23),
+                    SpecialDay {
+                        workday: true,
+                        name: "劳动节调休".to_string()
+                    }
+                ),
+                (
+                    date!(2023 - 05 - 01),
+                    SpecialDay {
+                        workday: false,
+                        name: "劳动节".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn workdays_follow_special_days() {
+        let db = temporary_db();
+        save(
+            &db,
+            &[
+                (
+                    date!(2023 - 04 - 23),
+                    SpecialDay {
+                        workday: true,
+                        name: "劳动节调休".to_string(),
+                    },
+                ),
+                (
+                    date!(2023 - 05 - 01),
+                    SpecialDay {
+                        workday: false,
+                        name: "劳动节".to_string(),
+                    },
+                ),
+            ],
+        )
+        .unwrap();
+
+        // 4月22日周六、23日周日调休上班，4月29、30日周末，5月1日周一放假
+        assert_eq!(
+            workdays(&db, date!(2023 - 04 - 21), date!(2023 - 05 - 02)).unwrap(),
+            vec![
+                date!(2023 - 04 - 21),
+                date!(2023 - 04 - 23),
+                date!(2023 - 04 - 24),
+                date!(2023 - 04 - 25),
+                date!(2023 - 04 - 26),
+                date!(2023 - 04 - 27),
+                date!(2023 - 04 - 28),
+                date!(2023 - 05 - 02),
+            ]
+        );
+        assert!(workdays(&db, date!(2023 - 05 - 01), date!(2023 - 04 - 30))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use liando::calendar;
+use liando::import::{undo_last_import, update_punches, update_record, update_statistics};
+use liando::input::UserInput;
+use liando::report::{default_output_name, generate_report};
+use liando::store;
+
+const USAGE: &str = "\
+用法：
+  liando import-statistics <每日统计表> [选项]
+  liando import-record <原始记录表> [选项]
+  liando import-punches <打卡记录> [选项]
+                                  导入每次打卡的时间，按班次计算首次、末次打卡和工作时长，
+                                  工作时长以打卡为准，上下班打卡结果以每日统计表为准
+  liando generate <模板> [--output <文件>] [选项]
+                                  生成报表，默认另存为模板所在目录下的“考勤_开始日期_结束日期.xlsx”，
+                                  --output与模板相同时覆盖模板，并先自动备份
+  liando import-calendar <节假日安排>  导入节假日和调休上班日，每行为“日期，休/班，名称”
+  liando undo                     撤销最近一次导入
+
+以上命令及不带命令启动界面时都可指定：
+  --db <路径>        数据库位置，默认取环境变量LIANDO_DB，否则为用户数据目录下的liando/liando.db
+                     工作目录或程序所在目录下有旧版本的liando.db时，首次运行自动复制到用户数据目录
+
+选项：
+  --config <文件>    从配置文件读取参数，每行一个“参数名 = 值”，#开头为注释
+  --<参数名> <值>    覆盖单项参数，优先于配置文件
+
+参数名：
+  start-date, end-date                      日期，如 2023-05-08
+  statistics-employee-id-col                每日统计表-工号列，如 D
+  statistics-date-col                       每日统计表-日期列
+  statistics-enter-result-col               每日统计表-上班打卡结果列
+  statistics-leave-result-col               每日统计表-下班打卡结果列
+  statistics-work-minutes-col               每日统计表-工作时长(分钟)列
+  statistics-start-row                      每日统计表-数据起始行号
+  statistics-header-row                     每日统计表-表头行号
+  statistics-*-header                       每日统计表-各列表头名称，*同列参数，如 statistics-date-header，
+                                            填写后导入时在表头行按名称查找列
+  record-employee-id-col                    原始记录表-工号列
+  record-date-col                           原始记录表-日期列
+  record-abnormal-reason-col                原始记录表-异常打卡原因列
+  record-start-row                          原始记录表-数据起始行号
+  record-header-row                         原始记录表-表头行号
+  record-*-header                           原始记录表-各列表头名称，同上
+  punch-employee-id-col                     打卡记录-工号列
+  punch-time-col                            打卡记录-打卡时间列，如 2023-05-08 08:55:12
+  punch-device-col                          打卡记录-打卡设备或地点列
+  punch-start-row                           打卡记录-数据起始行号
+  punch-header-row                          打卡记录-表头行号
+  punch-*-header                            打卡记录-各列表头名称，同上
+  shift-start, shift-end                    班次的上下班时间，如 9:00、18:00，晚于上班时间为迟到、早于下班时间为早退，
+                                            上班前的时间不计入工时
+  lunch-start, lunch-end                    午休时间，如 12:00、13:00，不计入工时，两者相同时不扣除
+  summary-columns                           是否在最后一天后添加汇总列，填“是”或“否”
+  clear-block                               是否在生成前清空本次要写的日期列和汇总列的内容，填“是”或“否”，
+                                            在上次生成的报表上重新生成时，上次多写的列也一并清空
+  no-data-text                              当天没有考勤记录时显示的文字，默认“无数据”，留空不写
+  style-preset                              报表样式，“微软雅黑9号居中”或“沿用模板样式”
+  template                                  模板配置，每个sheet为“工号列,数据起始列,表头行[,sheet名称]”，分号分隔，
+                                            如 A,G,2;D,I,2,研发部，不填sheet名称时按顺序对应
+  day-columns                               每天依次写的列，每列为“内容[,列宽[,表头]]”，分号分隔，
+                                            内容为工时、考勤、异常原因、上班打卡结果、下班打卡结果、加班、欠时、
+                                            首次打卡或末次打卡，
+                                            表头中\n为换行，可用以下占位符，如 考勤,15;工时,8,{date}（{weekday}）工时
+                                              {date}          日期，如 5月8日
+                                              {date:格式}     按格式写日期，yyyy、yy为年，mm、m为月，dd、d为日，
+                                                              如 {date:yyyy-mm-dd}
+                                              {weekday}       星期，如 周一
+                                              {week}          ISO周数
+  work-time-unit                            工时单位，分钟、小时或人天
+  rounding                                  工时取整方式，不取整、四舍五入、向下取整或向上取整
+  precision                                 工时精度，如 0.5 为取整到半个单位，同时决定显示几位小数，默认 0.01
+  standard-hours                            每天标准工时（小时），单位为人天时按它换算，默认 8
+  以上每日列和工时参数加“-序号”时只设置第几个模板配置，如 day-columns-2、work-time-unit-1
+  overtime                                  是否统计加班，填“是”或“否”，开启后添加“加班统计”sheet，
+                                            汇总列中加上工作日加班、休息日加班和欠时
+  overtime-workday-hours                    工作日的标准工时（小时），超出为加班、不足为欠时，默认 8
+  overtime-restday-hours                    休息日（周末和节假日）的标准工时（小时），默认 0
+
+未指定的参数沿用上次保存的输入。";
+
+enum Command {
+    ImportStatistics,
+    ImportRecord,
+    ImportPunches,
+    Generate,
+    ImportCalendar,
+}
+
+/// 从参数中取出`--db <路径>`，没有指定时为默认位置
+pub(crate) fn take_db_path(args: &mut Vec<String>) -> Result<PathBuf> {
+    let Some(index) = args.iter().position(|arg| arg == "--db") else {
+        return store::default_path();
+    };
+    if index + 1 >= args.len() {
+        return Err(anyhow!("参数 --db 缺少值"));
+    }
+    let path = args.remove(index + 1);
+    args.remove(index);
+    Ok(PathBuf::from(path))
+}
+
+/// 无界面模式：按命令行参数导入或生成，参数同设置页
+pub(crate) fn run(args: Vec<String>, db_path: &Path) -> Result<()> {
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        Some("import-statistics") => Command::ImportStatistics,
+        Some("import-record") => Command::ImportRecord,
+        Some("import-punches") => Command::ImportPunches,
+        Some("generate") => Command::Generate,
+        Some("import-calendar") => Command::ImportCalendar,
+        Some("undo") => return undo(db_path),
+        Some("help" | "-h" | "--help") => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        Some(other) => return Err(anyhow!("未知的命令：{other}\n\n{USAGE}")),
+        None => return Err(anyhow!(USAGE)),
+    };
+
+    let mut file = None;
+    let mut config = None;
+    let mut output = None;
+    let mut options = Vec::new();
+    while let Some(arg) = args.next() {
+        if let Some(key) = arg.strip_prefix("--") {
+            let value = args.next().ok_or(anyhow!("参数 --{key} 缺少值"))?;
+            if key == "config" {
+                config = Some(value);
+            } else if key == "output" {
+                // 只有生成报表会写文件，其他命令带上时报错，免得以为另存了
+                if !matches!(command, Command::Generate) {
+                    return Err(anyhow!("只有 generate 命令可以指定 --output"));
+                }
+                output = Some(PathBuf::from(value));
+            } else {
+                options.push((key.to_string(), value));
+            }
+        } else if file.is_none() {
+            file = Some(PathBuf::from(arg));
+        } else {
+            return Err(anyhow!("多余的参数：{arg}"));
+        }
+    }
+    let file = file.ok_or(anyhow!("缺少文件路径\n\n{USAGE}"))?;
+
+    let db = store::open(db_path)?;
+    let mut user_input = UserInput::load(&db)?;
+    if let Some(config) = config {
+        let content =
+            fs::read_to_string(&config).map_err(|e| anyhow!("读取配置文件{config}失败：{e}"))?;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(anyhow!("配置文件格式有误：{line}"))?;
+            user_input.set(key.trim(), value.trim())?;
+        }
+    }
+    for (key, value) in options {
+        user_input.set(&key, &value)?;
+    }
+    if user_input.start_date > user_input.end_date {
+        std::mem::swap(&mut user_input.start_date, &mut user_input.end_date);
+    }
+
+    match command {
+        Command::ImportStatistics => {
+            println!("{}", update_statistics(&file, &mut user_input, &db)?)
+        }
+        Command::ImportRecord => println!("{}", update_record(&file, &mut user_input, &db)?),
+        Command::ImportPunches => println!("{}", update_punches(&file, &mut user_input, &db)?),
+        Command::Generate => {
+            let output = match output {
+                Some(output) => output,
+                None => file.with_file_name(default_output_name(&user_input)?),
+            };
+            println!("{}", generate_report(&file, &output, &user_input, &db)?);
+        }
+        Command::ImportCalendar => println!("{}", calendar::import(&file, &db)?),
+    }
+    db.flush()?;
+
+    Ok(())
+}
+
+fn undo(db_path: &Path) -> Result<()> {
+    let db = store::open(db_path)?;
+    match undo_last_import(&db)? {
+        Some(changeset) => println!("已撤销：{changeset}"),
+        None => println!("没有可撤销的导入"),
+    }
+    db.flush()?;
+    Ok(())
+}
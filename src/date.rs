@@ -0,0 +1,169 @@
+use time::{Date, Duration, Month, PrimitiveDateTime, Time};
+
+/// Excel能表示的最后一天9999-12-31的序列号
+const MAX_SERIAL: f64 = 2958465.0;
+
+/// Excel日期序列号转为日期时间，小数部分为一天中的时间
+pub fn from_serial(serial: f64) -> Option<PrimitiveDateTime> {
+    if !(1.0..MAX_SERIAL + 1.0).contains(&serial) {
+        return None;
+    }
+
+    // 序列号1为1900-01-01，且Excel把1900年当作闰年，60以后的序列号从1899-12-30起算才对得上
+    let base = if serial < 60.0 {
+        Date::from_calendar_date(1899, Month::December, 31)
+    } else {
+        Date::from_calendar_date(1899, Month::December, 30)
+    }
+    .ok()?;
+    let days = serial.trunc() as i64;
+    let seconds = ((serial - serial.trunc()) * 86400.0).round() as i64;
+    Some(
+        PrimitiveDateTime::new(base, Time::MIDNIGHT)
+            + Duration::days(days)
+            + Duration::seconds(seconds),
+    )
+}
+
+/// 解析考勤日期单元格，支持Excel日期序列号，以及以下列格式开头的文本：
+/// `23-05-08 星期一`、`2023-05-08`、`2023/5/8`、`2023.5.8`、`2023年5月8日`、`20230508`
+pub fn parse_date_cell(text: &str, number: Option<f64>) -> Option<Date> {
+    match number.and_then(from_serial) {
+        Some(datetime) => Some(datetime.date()),
+        None => parse_date_text(text),
+    }
+}
+
+fn parse_date_text(text: &str) -> Option<Date> {
+    // 只取开头的日期部分，后面的星期、时间等忽略
+    let prefix = text
+        .trim()
+        .split(|c: char| !(c.is_ascii_digit() || "-/.年月日".contains(c)))
+        .next()?;
+    let parts = prefix
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+
+    let (year, month, day) = match parts[..] {
+        [ymd] if ymd.len() == 8 => (&ymd[..4], &ymd[4..6], &ymd[6..]),
+        [year, month, day] => (year, month, day),
+        _ => return None,
+    };
+    let year = match year.parse::<i32>().ok()? {
+        // 两位年份按20xx处理
+        year @ 0..=99 => year + 2000,
+        year => year,
+    };
+    let month = Month::try_from(month.parse::<u8>().ok()?).ok()?;
+    Date::from_calendar_date(year, month, day.parse::<u8>().ok()?).ok()
+}
+
+/// 解析打卡时间单元格，支持带小数的Excel日期序列号，以及“日期 时间”格式的文本，
+/// 日期格式同[`parse_date_cell`]，时间为`9:01`或`09:01:02`
+pub fn parse_datetime_cell(text: &str, number: Option<f64>) -> Option<PrimitiveDateTime> {
+    if let Some(datetime) = number.and_then(from_serial) {
+        return Some(datetime);
+    }
+    let text = text.trim();
+    let date = parse_date_text(text)?;
+    let time = text
+        .split(|c: char| c.is_whitespace() || c == 'T')
+        .map(str::trim)
+        .rfind(|part| part.contains(':'))?;
+    Some(PrimitiveDateTime::new(date, parse_time_text(time)?))
+}
+
+/// 解析`9:01`、`09:01:02`之类的时间
+pub fn parse_time_text(text: &str) -> Option<Time> {
+    let mut parts = text.trim().split(':');
+    let hour = parts.next()?.parse::<u8>().ok()?;
+    let minute = parts.next()?.parse::<u8>().ok()?;
+    let second = match parts.next() {
+        Some(second) => second.parse::<u8>().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Time::from_hms(hour, minute, second).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[test]
+    fn serial_around_1900_leap_day() {
+        let day = |serial| from_serial(serial).map(|datetime| datetime.date());
+        assert_eq!(day(1.0), Some(date!(1900 - 01 - 01)));
+        assert_eq!(day(59.0), Some(date!(1900 - 02 - 28)));
+        // Excel中不存在的1900-02-29，落到2月28日
+        assert_eq!(day(60.0), Some(date!(1900 - 02 - 28)));
+        assert_eq!(day(61.0), Some(date!(1900 - 03 - 01)));
+        assert_eq!(day(45054.0), Some(date!(2023 - 05 - 08)));
+        assert_eq!(day(0.5), None);
+        assert_eq!(day(MAX_SERIAL + 1.0), None);
+    }
+
+    #[test]
+    fn serial_keeps_time_of_day() {
+        assert_eq!(
+            from_serial(45054.375),
+            Some(datetime!(2023 - 05 - 08 09:00:00))
+        );
+    }
+
+    #[test]
+    fn date_text_formats() {
+        let expected = Some(date!(2023 - 05 - 08));
+        for text in [
+            "23-05-08 星期一",
+            "2023-05-08",
+            "2023/5/8",
+            "2023.5.8",
+            "2023年5月8日",
+            "2023年5月8日 星期一",
+            "20230508",
+            " 2023-05-08 09:01:02 ",
+        ] {
+            assert_eq!(parse_date_cell(text, None), expected, "{text}");
+        }
+        assert_eq!(parse_date_cell("2023-02-30", None), None);
+        assert_eq!(parse_date_cell("星期一", None), None);
+        assert_eq!(parse_date_cell("2023-05", None), None);
+    }
+
+    #[test]
+    fn number_takes_precedence_over_text() {
+        assert_eq!(
+            parse_date_cell("2023-05-08", Some(45055.0)),
+            Some(date!(2023 - 05 - 09))
+        );
+        // 非日期的数值按文本解析
+        assert_eq!(
+            parse_date_cell("2023-05-08", Some(0.0)),
+            Some(date!(2023 - 05 - 08))
+        );
+    }
+
+    #[test]
+    fn datetime_text_formats() {
+        assert_eq!(
+            parse_datetime_cell("2023-05-08 09:01:02", None),
+            Some(datetime!(2023 - 05 - 08 09:01:02))
+        );
+        assert_eq!(
+            parse_datetime_cell("2023/5/8 9:07", None),
+            Some(datetime!(2023 - 05 - 08 09:07:00))
+        );
+        assert_eq!(
+            parse_datetime_cell("2023-05-08T18:30:00", None),
+            Some(datetime!(2023 - 05 - 08 18:30:00))
+        );
+        assert_eq!(parse_datetime_cell("2023-05-08", None), None);
+        assert_eq!(parse_datetime_cell("2023-05-08 25:00", None), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! 报表单元格高亮规则：考勤文字包含关键词或工时低于阈值时，给单元格加填充色和字体颜色。
+//! 规则列表存在sled的`highlight_rules`键下，没有保存过时使用默认规则。
+//! 增删字段需同时升级数据库版本，见[`crate::migrate`]
+
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+
+pub(crate) const HIGHLIGHT_RULES_KEY: &str = "highlight_rules";
+
+/// 一条高亮规则，按列表顺序取第一条匹配的规则
+#[derive(Debug, Clone, Default, Readable, Writable, PartialEq)]
+pub struct HighlightRule {
+    /// 考勤单元格的文字或原始打卡结果、异常原因包含的关键词，多个用“|”分隔，为空时不按关键词匹配
+    pub pattern: String,
+    /// 工时单元格低于这个小时数时匹配，为0时不按工时匹配
+    pub hours_below: f64,
+    /// 填充色，6位十六进制RGB如FFC7CE，为空时不填充
+    pub fill_color: String,
+    /// 字体颜色，格式同上，为空时不改
+    pub font_color: String,
+}
+
+impl HighlightRule {
+    fn new(pattern: &str, hours_below: f64, fill_color: &str, font_color: &str) -> Self {
+        HighlightRule {
+            pattern: pattern.to_string(),
+            hours_below,
+            fill_color: fill_color.to_string(),
+            font_color: font_color.to_string(),
+        }
+    }
+}
+
+/// 默认规则：缺卡、旷工标红，迟到、早退标橙，工时不足8小时标蓝
+pub fn default_rules() -> Vec<HighlightRule> {
+    vec![
+        HighlightRule::new("缺卡|旷工", 0.0, "FFC7CE", "9C0006"),
+        HighlightRule::new("迟到|早退", 0.0, "FFCC99", "9C5700"),
+        HighlightRule::new("", 8.0, "BDD7EE", "1F4E79"),
+    ]
+}
+
+/// 读取保存的规则；没有保存过时为默认规则
+///
+/// 保存的规则读不出来时报错，不用默认规则代替，以免之后保存时覆盖原有的规则
+pub fn load(db: &Db) -> Result<Vec<HighlightRule>> {
+    match db.get(HIGHLIGHT_RULES_KEY)? {
+        Some(value) => Vec::<HighlightRule>::read_from_buffer(&value)
+            .map_err(|e| anyhow!("读取保存的高亮规则失败：{e}，请备份数据库后联系开发者")),
+        None => Ok(default_rules()),
+    }
+}
+
+pub fn save(db: &Db, rules: &[HighlightRule]) -> Result<()> {
+    db.insert(HIGHLIGHT_RULES_KEY, rules.to_vec().write_to_vec()?)?;
+    Ok(())
+}
+
+/// 考勤单元格匹配的规则，`texts`为单元格文字及原始打卡结果、异常原因
+pub fn for_status<'a>(rules: &'a [HighlightRule], texts: &[&str]) -> Option<&'a HighlightRule> {
+    rules.iter().find(|rule| {
+        rule.pattern
+            .split('|')
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .any(|keyword| texts.iter().any(|text| text.contains(keyword)))
+    })
+}
+
+/// 工时单元格匹配的规则
+pub fn for_hours(rules: &[HighlightRule], hours: f64) -> Option<&HighlightRule> {
+    rules
+        .iter()
+        .find(|rule| rule.hours_below > 0.0 && hours < rule.hours_below)
+}
+
+/// 把“#ff0000”、“FF0000”之类的颜色规范为6位大写十六进制，空为空
+pub fn parse_color(text: &str) -> Result<String> {
+    let color = text.trim().trim_start_matches('#').to_ascii_uppercase();
+    if color.is_empty() || (color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit())) {
+        Ok(color)
+    } else {
+        Err(anyhow!(
+            "颜色填写有误：{text}，请填写6位十六进制RGB，如FF0000"
+        ))
+    }
+}
@@ -0,0 +1,610 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use sled::transaction::{ConflictableTransactionError, Transactional};
+use sled::{Batch, Db};
+use speedy::{Readable, Writable};
+use time::{macros::format_description, Date, OffsetDateTime, UtcOffset};
+
+use crate::input::{UserInput, USER_INPUT_KEY};
+use crate::punch::{day_punches, Punch, PUNCH_TREE};
+use crate::store::Attendance;
+use crate::table::Table;
+
+/// 存放导入历史的sled tree，键为递增的id
+pub(crate) const HISTORY_TREE: &str = "import_history";
+/// 最多保留最近多少次导入可供撤销
+const HISTORY_LIMIT: usize = 20;
+
+/// 一次导入的结果，Display输出可直接展示给用户
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    /// 读取的数据行数，不含空行
+    pub rows_read: u32,
+    /// 新增的考勤记录数
+    pub created: u32,
+    /// 更新的考勤记录数
+    pub updated: u32,
+    /// 跳过的行
+    pub skipped: Vec<SkippedRow>,
+    /// 导入数据的最早和最晚日期
+    pub date_range: Option<(Date, Date)>,
+}
+
+/// 未导入的行及原因
+#[derive(Debug, PartialEq)]
+pub struct SkippedRow {
+    pub row: u32,
+    pub reason: String,
+}
+
+impl ImportSummary {
+    pub(crate) fn imported(&mut self, date: Date, created: bool) {
+        if created {
+            self.created += 1;
+        } else {
+            self.updated += 1;
+        }
+        self.date_range = match self.date_range {
+            Some((start, end)) => Some((start.min(date), end.max(date))),
+            None => Some((date, date)),
+        };
+    }
+
+    pub(crate) fn skip(&mut self, row: u32, reason: impl Into<String>) {
+        self.skipped.push(SkippedRow {
+            row,
+            reason: reason.into(),
+        });
+    }
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "导入完成：读取{}行，新增{}条，更新{}条，跳过{}行",
+            self.rows_read,
+            self.created,
+            self.updated,
+            self.skipped.len()
+        )?;
+        if let Some((start, end)) = self.date_range {
+            write!(f, "\n日期范围：{start} 至 {end}")?;
+        }
+        if !self.skipped.is_empty() {
+            write!(f, "\n跳过的行：")?;
+            for skipped in &self.skipped {
+                write!(f, "\n  第{}行：{}", skipped.row, skipped.reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 导入每日统计表（xlsx、xls或csv）的上下班打卡结果和工作时长，已按打卡算出工作时长的不覆盖
+///
+/// 填写了表头名称的列按名称重新定位，定位结果写回`user_input`
+pub fn update_statistics(
+    path: impl AsRef<Path>,
+    user_input: &mut UserInput,
+    db: &Db,
+) -> Result<ImportSummary> {
+    let path = path.as_ref();
+    let worksheet = Table::read(path)?;
+    let header_row = user_input.statistics_header_row;
+    for (header, col) in [
+        (
+            &user_input.statistics_employee_id_header,
+            &mut user_input.statistics_employee_id_col,
+        ),
+        (
+            &user_input.statistics_date_header,
+            &mut user_input.statistics_date_col,
+        ),
+        (
+            &user_input.statistics_enter_result_header,
+            &mut user_input.statistics_enter_result_col,
+        ),
+        (
+            &user_input.statistics_leave_result_header,
+            &mut user_input.statistics_leave_result_col,
+        ),
+        (
+            &user_input.statistics_work_minutes_header,
+            &mut user_input.statistics_work_minutes_col,
+        ),
+    ] {
+        locate_col(&worksheet, header_row, header, col)?;
+    }
+    let max_row = worksheet.get_highest_row();
+    let mut summary = ImportSummary::default();
+    let mut staging = Staging::new(db);
+
+    for r in user_input.statistics_start_row..max_row + 1 {
+        if worksheet.is_row_empty(r) {
+            continue;
+        }
+        summary.rows_read += 1;
+
+        let employee_id = worksheet.get_formatted_value((user_input.statistics_employee_id_col, r));
+        if employee_id.is_empty() {
+            summary.skip(r, "工号为空");
+            continue;
+        }
+
+        if let Some(attendance_date) = worksheet.get_date((user_input.statistics_date_col, r)) {
+            let attendance = staging.get_mut(format!("{attendance_date}_{employee_id}"))?;
+            summary.imported(attendance_date, attendance.employee_id.is_empty());
+            attendance.employee_id = employee_id;
+            let enter_info =
+                worksheet.get_formatted_value((user_input.statistics_enter_result_col, r));
+            let leave_info =
+                worksheet.get_formatted_value((user_input.statistics_leave_result_col, r));
+            // 统计表中没有结果时保留按打卡算出的结果
+            if !(enter_info.is_empty() && leave_info.is_empty()) {
+                attendance.enter_info = enter_info;
+                attendance.leave_info = leave_info;
+                attendance.results_from_punches = false;
+            }
+            // 导入过打卡记录的以打卡算出的工作时长为准
+            if !attendance.minutes_from_punches {
+                attendance.work_minutes = worksheet
+                    .get_value_number((user_input.statistics_work_minutes_col, r))
+                    .unwrap_or_default();
+            }
+        } else {
+            let date = worksheet.get_formatted_value((user_input.statistics_date_col, r));
+            summary.skip(r, format!("日期无法识别：{date}"));
+        }
+    }
+
+    staging.commit(user_input, path)?;
+    Ok(summary)
+}
+
+/// 导入原始记录表（xlsx、xls或csv）的异常打卡原因
+///
+/// 填写了表头名称的列按名称重新定位，定位结果写回`user_input`
+pub fn update_record(
+    path: impl AsRef<Path>,
+    user_input: &mut UserInput,
+    db: &Db,
+) -> Result<ImportSummary> {
+    let path = path.as_ref();
+    let worksheet = Table::read(path)?;
+    let header_row = user_input.record_header_row;
+    for (header, col) in [
+        (
+            &user_input.record_employee_id_header,
+            &mut user_input.record_employee_id_col,
+        ),
+        (
+            &user_input.record_date_header,
+            &mut user_input.record_date_col,
+        ),
+        (
+            &user_input.record_abnormal_reason_header,
+            &mut user_input.record_abnormal_reason_col,
+        ),
+    ] {
+        locate_col(&worksheet, header_row, header, col)?;
+    }
+    let max_row = worksheet.get_highest_row();
+    let mut summary = ImportSummary::default();
+    let mut staging = Staging::new(db);
+
+    for r in user_input.record_start_row..max_row + 1 {
+        if worksheet.is_row_empty(r) {
+            continue;
+        }
+        summary.rows_read += 1;
+
+        let employee_id = worksheet.get_formatted_value((user_input.record_employee_id_col, r));
+        if employee_id.is_empty() {
+            summary.skip(r, "工号为空");
+            continue;
+        }
+
+        if let Some(attendance_date) = worksheet.get_date((user_input.record_date_col, r)) {
+            let attendance = staging.get_mut(format!("{attendance_date}_{employee_id}"))?;
+            summary.imported(attendance_date, attendance.employee_id.is_empty());
+            attendance.employee_id = employee_id;
+            attendance.abnormal_reason =
+                worksheet.get_formatted_value((user_input.record_abnormal_reason_col, r));
+        } else {
+            let date = worksheet.get_formatted_value((user_input.record_date_col, r));
+            summary.skip(r, format!("日期无法识别：{date}"));
+        }
+    }
+
+    staging.commit(user_input, path)?;
+    Ok(summary)
+}
+
+/// 导入原始打卡记录（xlsx、xls或csv），逐条保存打卡，并按班次重新计算涉及的每一天的
+/// 首次、末次打卡和工作时长，计算时包括以前导入的同一天的打卡；
+/// 上下班打卡结果何时重新计算见[`crate::punch::Shift::apply`]
+///
+/// 填写了表头名称的列按名称重新定位，定位结果写回`user_input`
+pub fn update_punches(
+    path: impl AsRef<Path>,
+    user_input: &mut UserInput,
+    db: &Db,
+) -> Result<ImportSummary> {
+    user_input.shift.check()?;
+    let path = path.as_ref();
+    let worksheet = Table::read(path)?;
+    let header_row = user_input.punch_header_row;
+    for (header, col) in [
+        (
+            &user_input.punch_employee_id_header,
+            &mut user_input.punch_employee_id_col,
+        ),
+        (
+            &user_input.punch_time_header,
+            &mut user_input.punch_time_col,
+        ),
+        (
+            &user_input.punch_device_header,
+            &mut user_input.punch_device_col,
+        ),
+    ] {
+        locate_col(&worksheet, header_row, header, col)?;
+    }
+    let max_row = worksheet.get_highest_row();
+    let mut summary = ImportSummary::default();
+    let mut staging = Staging::new(db);
+    let mut days = BTreeSet::new();
+
+    for r in user_input.punch_start_row..max_row + 1 {
+        if worksheet.is_row_empty(r) {
+            continue;
+        }
+        summary.rows_read += 1;
+
+        let employee_id = worksheet.get_formatted_value((user_input.punch_employee_id_col, r));
+        if employee_id.is_empty() {
+            summary.skip(r, "工号为空");
+            continue;
+        }
+
+        if let Some(datetime) = worksheet.get_datetime((user_input.punch_time_col, r)) {
+            let (hour, minute, second) = datetime.time().as_hms();
+            let punch = Punch {
+                employee_id: employee_id.clone(),
+                seconds: hour as u32 * 3600 + minute as u32 * 60 + second as u32,
+                device: worksheet.get_formatted_value((user_input.punch_device_col, r)),
+            };
+            staging.add_punch(datetime.date(), punch);
+            days.insert((datetime.date(), employee_id));
+        } else {
+            let time = worksheet.get_formatted_value((user_input.punch_time_col, r));
+            summary.skip(r, format!("打卡时间无法识别：{time}"));
+        }
+    }
+
+    for (date, employee_id) in days {
+        let seconds = staging.day_punches(date, &employee_id)?;
+        let attendance = staging.get_mut(format!("{date}_{employee_id}"))?;
+        summary.imported(date, attendance.employee_id.is_empty());
+        attendance.employee_id = employee_id;
+        user_input.shift.apply(&seconds, attendance);
+    }
+
+    staging.commit(user_input, path)?;
+    Ok(summary)
+}
+
+/// 一次导入改动的考勤记录及其导入前的值，用于撤销
+#[derive(Debug, Readable, Writable, PartialEq)]
+pub struct Changeset {
+    /// 导入的文件
+    pub source: String,
+    /// 导入时间，unix时间戳
+    pub imported_at: i64,
+    /// 导入前的考勤记录，None表示原本没有这条记录
+    pub previous: Vec<(String, Option<Attendance>)>,
+    /// 这次新增的打卡记录的键
+    pub added_punches: Vec<String>,
+}
+
+impl fmt::Display for Changeset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let imported_at = OffsetDateTime::from_unix_timestamp(self.imported_at)
+            .map_err(|_| fmt::Error)?
+            .to_offset(UtcOffset::from_hms(8, 0, 0).unwrap())
+            .format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second]"
+            ))
+            .map_err(|_| fmt::Error)?;
+        write!(
+            f,
+            "{imported_at} 导入的 {}，共{}条考勤记录",
+            self.source,
+            self.previous.len()
+        )?;
+        if !self.added_punches.is_empty() {
+            write!(f, "、{}条打卡", self.added_punches.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// 撤销最近一次导入，把它改动的考勤记录恢复为导入前的值并删除新增的打卡，返回被撤销的导入；
+/// 没有可撤销的导入时返回None
+pub fn undo_last_import(db: &Db) -> Result<Option<Changeset>> {
+    let history = db.open_tree(HISTORY_TREE)?;
+    let Some((id, value)) = history.last()? else {
+        return Ok(None);
+    };
+    let changeset = Changeset::read_from_buffer(&value)?;
+
+    let mut batch = Batch::default();
+    for (key, previous) in &changeset.previous {
+        match previous {
+            Some(attendance) => batch.insert(key.as_str(), attendance.write_to_vec()?),
+            None => batch.remove(key.as_str()),
+        }
+    }
+    let mut punch_batch = Batch::default();
+    for key in &changeset.added_punches {
+        punch_batch.remove(key.as_str());
+    }
+    let punches = db.open_tree(PUNCH_TREE)?;
+    (&**db, &history, &punches)
+        .transaction(|(data, history, punches)| {
+            data.apply_batch(&batch)?;
+            punches.apply_batch(&punch_batch)?;
+            history.remove(&id)?;
+            Ok::<_, ConflictableTransactionError>(())
+        })
+        .map_err(|e| anyhow!("撤销导入失败：{e}"))?;
+
+    Ok(Some(changeset))
+}
+
+/// 暂存一次导入的全部改动，读完整个文件后和输入、导入历史一起在一个事务中写入，
+/// 中途出错时数据库保持原样
+struct Staging<'a> {
+    db: &'a Db,
+    /// 键 -> （导入前的值，导入后的值）
+    changes: BTreeMap<String, (Option<Attendance>, Attendance)>,
+    /// 导入的打卡，键 -> 打卡
+    punches: BTreeMap<String, Punch>,
+}
+
+impl<'a> Staging<'a> {
+    fn new(db: &'a Db) -> Self {
+        Staging {
+            db,
+            changes: BTreeMap::new(),
+            punches: BTreeMap::new(),
+        }
+    }
+
+    fn add_punch(&mut self, date: Date, punch: Punch) {
+        self.punches.insert(punch.key(date), punch);
+    }
+
+    /// 某员工某天的全部打卡时间（秒），包括数据库里已有的和暂存的
+    fn day_punches(&self, date: Date, employee_id: &str) -> Result<Vec<u32>> {
+        let mut punches = day_punches(self.db, date, employee_id)?
+            .into_iter()
+            .map(|punch| (punch.key(date), punch))
+            .collect::<BTreeMap<_, _>>();
+        let prefix = format!("{date}_{employee_id}_");
+        punches.extend(
+            self.punches
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .filter(|(_, punch)| punch.employee_id == employee_id)
+                .map(|(key, punch)| (key.clone(), punch.clone())),
+        );
+        Ok(punches.values().map(|punch| punch.seconds).collect())
+    }
+
+    /// 取暂存的考勤，第一次取时从数据库读出，数据库里没有则为默认值（工号为空）
+    ///
+    /// 已有的记录读不出来时报错，不当作没有，否则导入会覆盖它，撤销时又会删掉它
+    fn get_mut(&mut self, key: String) -> Result<&mut Attendance> {
+        if !self.changes.contains_key(&key) {
+            let previous = match self.db.get(&key)? {
+                Some(value) => Some(Attendance::read_from_buffer(&value).map_err(|e| {
+                    anyhow!("考勤记录{key}无法读取：{e}，请备份数据库后联系开发者")
+                })?),
+                None => None,
+            };
+            let attendance = previous.clone().unwrap_or_default();
+            self.changes.insert(key.clone(), (previous, attendance));
+        }
+        Ok(&mut self.changes.get_mut(&key).unwrap().1)
+    }
+
+    fn commit(self, user_input: &UserInput, source: &Path) -> Result<()> {
+        let mut batch = Batch::default();
+        batch.insert(USER_INPUT_KEY, user_input.write_to_vec()?);
+        let mut previous = Vec::with_capacity(self.changes.len());
+        for (key, (old, attendance)) in self.changes {
+            batch.insert(key.as_str(), attendance.write_to_vec()?);
+            previous.push((key, old));
+        }
+
+        let punches = self.db.open_tree(PUNCH_TREE)?;
+        let mut punch_batch = Batch::default();
+        let mut added_punches = Vec::new();
+        for (key, punch) in self.punches {
+            if !punches.contains_key(&key)? {
+                added_punches.push(key.clone());
+            }
+            punch_batch.insert(key.as_str(), punch.write_to_vec()?);
+        }
+
+        let changeset = Changeset {
+            source: source.display().to_string(),
+            imported_at: OffsetDateTime::now_utc().unix_timestamp(),
+            previous,
+            added_punches,
+        }
+        .write_to_vec()?;
+        let id = self.db.generate_id()?.to_be_bytes();
+        let history = self.db.open_tree(HISTORY_TREE)?;
+        let expired = history
+            .iter()
+            .keys()
+            .rev()
+            .skip(HISTORY_LIMIT - 1)
+            .collect::<sled::Result<Vec<_>>>()?;
+
+        (&**self.db, &history, &punches)
+            .transaction(|(data, history, punches)| {
+                data.apply_batch(&batch)?;
+                punches.apply_batch(&punch_batch)?;
+                history.insert(&id, changeset.as_slice())?;
+                for key in &expired {
+                    history.remove(key)?;
+                }
+                Ok::<_, ConflictableTransactionError>(())
+            })
+            .map_err(|e| anyhow!("保存导入结果失败：{e}"))?;
+        Ok(())
+    }
+}
+
+fn locate_col(worksheet: &Table, header_row: u32, header: &str, col: &mut u32) -> Result<()> {
+    if !header.trim().is_empty() {
+        *col = worksheet.find_col(header_row, header).ok_or(anyhow!(
+            "第{header_row}行找不到表头“{header}”，请检查表头名称或表头行号"
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use super::*;
+
+    fn temporary_db() -> Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    /// 写一个测试用的csv文件，文件名带进程号，避免与同时运行的测试冲突
+    fn write_csv(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("liando_{}_{name}.csv", process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// 每日统计表为“工号,日期,上班打卡结果,下班打卡结果,工作时长”，打卡记录为“工号,打卡时间”
+    fn user_input() -> UserInput {
+        UserInput {
+            statistics_employee_id_col: 1,
+            statistics_date_col: 2,
+            statistics_enter_result_col: 3,
+            statistics_leave_result_col: 4,
+            statistics_work_minutes_col: 5,
+            statistics_start_row: 2,
+            statistics_header_row: 1,
+            ..Default::default()
+        }
+    }
+
+    fn attendance(db: &Db, key: &str) -> Option<Attendance> {
+        db.get(key)
+            .unwrap()
+            .map(|value| Attendance::read_from_buffer(&value).unwrap())
+    }
+
+    fn punch_keys(db: &Db) -> Vec<String> {
+        db.open_tree(PUNCH_TREE)
+            .unwrap()
+            .iter()
+            .keys()
+            .map(|key| String::from_utf8(key.unwrap().to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn undo_restores_previous_values_and_removes_created_records() {
+        let db = temporary_db();
+        let mut user_input = user_input();
+        let first = write_csv(
+            "undo_first",
+            "工号,日期,上班,下班,时长\n1001,2023-05-08,正常,正常,480\n",
+        );
+        update_statistics(&first, &mut user_input, &db).unwrap();
+        let before = attendance(&db, "2023-05-08_1001").unwrap();
+
+        let second = write_csv(
+            "undo_second",
+            "工号,日期,上班,下班,时长\n1001,2023-05-08,请假,正常,240\n1002,2023-05-08,正常,正常,480\n",
+        );
+        let summary = update_statistics(&second, &mut user_input, &db).unwrap();
+        assert_eq!((summary.created, summary.updated), (1, 1));
+        assert_eq!(
+            attendance(&db, "2023-05-08_1001").unwrap().enter_info,
+            "请假"
+        );
+
+        let changeset = undo_last_import(&db).unwrap().unwrap();
+        assert_eq!(changeset.source, second.display().to_string());
+        assert_eq!(attendance(&db, "2023-05-08_1001"), Some(before));
+        assert_eq!(attendance(&db, "2023-05-08_1002"), None);
+
+        undo_last_import(&db).unwrap().unwrap();
+        assert_eq!(attendance(&db, "2023-05-08_1001"), None);
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn undo_removes_only_added_punches() {
+        let db = temporary_db();
+        let mut user_input = user_input();
+        let first = write_csv("punch_first", "工号,打卡时间\n1001,2023-05-08 08:55:00\n");
+        update_punches(&first, &mut user_input, &db).unwrap();
+        let after_first = attendance(&db, "2023-05-08_1001").unwrap();
+
+        // 第二次导入重复了第一次的打卡
+        let second = write_csv(
+            "punch_second",
+            "工号,打卡时间\n1001,2023-05-08 08:55:00\n1001,2023-05-08 18:05:00\n",
+        );
+        update_punches(&second, &mut user_input, &db).unwrap();
+        assert_eq!(punch_keys(&db).len(), 2);
+
+        let changeset = undo_last_import(&db).unwrap().unwrap();
+        assert_eq!(changeset.added_punches, vec!["2023-05-08_1001_18:05:00"]);
+        assert_eq!(punch_keys(&db), vec!["2023-05-08_1001_08:55:00"]);
+        assert_eq!(attendance(&db, "2023-05-08_1001"), Some(after_first));
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn undo_without_history() {
+        // 界面和命令行据此提示“没有可撤销的导入”
+        assert!(undo_last_import(&temporary_db()).unwrap().is_none());
+    }
+
+    #[test]
+    fn unreadable_record_stops_import() {
+        let db = temporary_db();
+        db.insert("2023-05-08_1001", b"garbage".as_slice()).unwrap();
+        let path = write_csv(
+            "unreadable",
+            "工号,日期,上班,下班,时长\n1001,2023-05-08,正常,正常,480\n",
+        );
+        assert!(update_statistics(&path, &mut user_input(), &db).is_err());
+        assert_eq!(
+            db.get("2023-05-08_1001").unwrap().unwrap(),
+            b"garbage".as_slice()
+        );
+        assert!(undo_last_import(&db).unwrap().is_none());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+use time::{macros::format_description, Date, Duration};
+use time::{OffsetDateTime, UtcOffset};
+use umya_spreadsheet::helper::coordinate::column_index_from_string;
+
+use crate::overtime::OvertimeRule;
+use crate::punch::{parse_minutes, Shift};
+use crate::report::{
+    default_day_columns, parse_day_columns, DayColumn, Rounding, StylePreset, WorkTime,
+    WorkTimeUnit,
+};
+
+/// 保存输入的键
+pub const USER_INPUT_KEY: &str = "user_input";
+
+/// 每个模板配置各自的参数，加“-序号”时只设置第几个模板配置，如 day-columns-2
+const TEMPLATE_KEYS: [&str; 5] = [
+    "day-columns",
+    "work-time-unit",
+    "rounding",
+    "precision",
+    "standard-hours",
+];
+
+/// 设置页的全部输入，列号、行号均从1开始，日期为儒略日
+///
+/// 增删字段需同时升级数据库版本，见[`crate::migrate`]
+#[derive(Debug, Readable, Writable, PartialEq)]
+pub struct UserInput {
+    pub start_date: i32,
+    pub end_date: i32,
+    pub statistics_employee_id_col: u32,
+    pub statistics_date_col: u32,
+    pub statistics_enter_result_col: u32,
+    pub statistics_leave_result_col: u32,
+    pub statistics_work_minutes_col: u32,
+    pub statistics_start_row: u32,
+    /// 表头所在行，下面的表头名称不为空时，导入时按名称在这一行查找对应列
+    pub statistics_header_row: u32,
+    pub statistics_employee_id_header: String,
+    pub statistics_date_header: String,
+    pub statistics_enter_result_header: String,
+    pub statistics_leave_result_header: String,
+    pub statistics_work_minutes_header: String,
+    pub record_employee_id_col: u32,
+    pub record_date_col: u32,
+    pub record_abnormal_reason_col: u32,
+    pub record_start_row: u32,
+    pub record_header_row: u32,
+    pub record_employee_id_header: String,
+    pub record_date_header: String,
+    pub record_abnormal_reason_header: String,
+    /// 每个模板sheet的配置
+    pub template_cfg: Vec<SheetConfig>,
+    /// 在最后一天之后加上合计工时、日均工时、异常天数等汇总列
+    pub summary_columns: bool,
+    /// 报表单元格的样式
+    pub style_preset: StylePreset,
+    /// 生成前清空数据区域，即本次要写的日期列和汇总列、在上次生成的报表上重新生成时上次写过的列，
+    /// 从表头行到最后一行，工号列不动
+    pub clear_block: bool,
+    /// 当天没有考勤记录的员工在考勤单元格显示的文字，为空时不写
+    pub no_data_text: String,
+    /// 加班统计
+    pub overtime: OvertimeRule,
+    pub punch_employee_id_col: u32,
+    pub punch_time_col: u32,
+    pub punch_device_col: u32,
+    pub punch_start_row: u32,
+    pub punch_header_row: u32,
+    pub punch_employee_id_header: String,
+    pub punch_time_header: String,
+    pub punch_device_header: String,
+    /// 由打卡记录计算工时用的班次
+    pub shift: Shift,
+}
+
+/// 一个模板sheet的配置，列号、行号从1开始
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct SheetConfig {
+    /// 工号所在列
+    pub employee_id_col: u32,
+    /// 第一天的数据所在列
+    pub start_col: u32,
+    /// 表头所在行
+    pub title_row: u32,
+    /// 要写入的sheet名称，为空时按配置的顺序对应第几个sheet
+    pub sheet_name: String,
+    /// 每天依次写的列
+    pub day_columns: Vec<DayColumn>,
+    /// 工时的单位和取整
+    pub work_time: WorkTime,
+}
+
+impl SheetConfig {
+    pub fn new(employee_id_col: u32, start_col: u32, title_row: u32) -> Self {
+        SheetConfig {
+            employee_id_col,
+            start_col,
+            title_row,
+            sheet_name: String::new(),
+            day_columns: default_day_columns(),
+            work_time: WorkTime::default(),
+        }
+    }
+}
+
+impl Default for SheetConfig {
+    fn default() -> Self {
+        SheetConfig::new(1, 7, 2)
+    }
+}
+
+impl Default for UserInput {
+    fn default() -> Self {
+        let today = OffsetDateTime::now_utc().to_offset(UtcOffset::from_hms(8, 0, 0).unwrap());
+        let last_friday = today.saturating_sub(Duration::days(
+            today.weekday().number_days_from_sunday() as i64 + 2_i64,
+        ));
+        let last_monday = last_friday.saturating_sub(Duration::days(4));
+
+        UserInput {
+            start_date: last_monday.to_julian_day(),
+            end_date: last_friday.to_julian_day(),
+            statistics_employee_id_col: 4,
+            statistics_date_col: 7,
+            statistics_enter_result_col: 10,
+            statistics_leave_result_col: 12,
+            statistics_work_minutes_col: 20,
+            statistics_start_row: 5,
+            statistics_header_row: 4,
+            statistics_employee_id_header: String::new(),
+            statistics_date_header: String::new(),
+            statistics_enter_result_header: String::new(),
+            statistics_leave_result_header: String::new(),
+            statistics_work_minutes_header: String::new(),
+            record_employee_id_col: 4,
+            record_date_col: 7,
+            record_abnormal_reason_col: 13,
+            record_start_row: 4,
+            record_header_row: 3,
+            record_employee_id_header: String::new(),
+            record_date_header: String::new(),
+            record_abnormal_reason_header: String::new(),
+            template_cfg: vec![
+                SheetConfig::new(1, 7, 2),
+                SheetConfig::new(4, 9, 2),
+                SheetConfig::new(4, 9, 2),
+            ],
+            summary_columns: false,
+            style_preset: StylePreset::default(),
+            clear_block: false,
+            no_data_text: "无数据".to_string(),
+            overtime: OvertimeRule::default(),
+            punch_employee_id_col: 1,
+            punch_time_col: 2,
+            punch_device_col: 3,
+            punch_start_row: 2,
+            punch_header_row: 1,
+            punch_employee_id_header: String::new(),
+            punch_time_header: String::new(),
+            punch_device_header: String::new(),
+            shift: Shift::default(),
+        }
+    }
+}
+
+impl UserInput {
+    /// 读取上次保存的输入，没有保存过时使用默认值
+    ///
+    /// 保存的输入读不出来时报错，不用默认值代替，以免之后保存时覆盖原有的设置
+    pub fn load(db: &Db) -> Result<Self> {
+        match db.get(USER_INPUT_KEY)? {
+            Some(value) => UserInput::read_from_buffer(&value)
+                .map_err(|e| anyhow!("读取保存的输入失败：{e}，请备份数据库后联系开发者")),
+            None => Ok(UserInput::default()),
+        }
+    }
+
+    /// 按名称修改单项输入，供命令行参数和配置文件使用
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let col = || parse_col(value).ok_or(anyhow!("{key}，列号填写有误：{value}"));
+        let row = || {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|&row| row > 0)
+                .ok_or(anyhow!("{key}，行号填写有误：{value}"))
+        };
+        let text = || value.trim().to_string();
+        let flag = || match value.trim() {
+            "是" | "true" | "yes" | "1" => Ok(true),
+            "否" | "false" | "no" | "0" => Ok(false),
+            _ => Err(anyhow!("{key}，请填写“是”或“否”：{value}")),
+        };
+        let hours = || {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|&hours| (0.0..=24.0).contains(&hours))
+                .ok_or(anyhow!("{key}，请填写0到24之间的小时数：{value}"))
+        };
+        let minutes = || parse_minutes(value).ok_or(anyhow!("{key}，时间填写有误：{value}"));
+        let date = || {
+            Date::parse(value, format_description!("[year]-[month]-[day]"))
+                .map(|date| date.to_julian_day())
+                .map_err(|_| anyhow!("{key}，日期填写有误：{value}"))
+        };
+
+        if let Some((name, index)) = split_template_key(key) {
+            return self.set_template(key, name, index, value);
+        }
+
+        match key {
+            "start-date" => self.start_date = date()?,
+            "end-date" => self.end_date = date()?,
+            "statistics-employee-id-col" => self.statistics_employee_id_col = col()?,
+            "statistics-date-col" => self.statistics_date_col = col()?,
+            "statistics-enter-result-col" => self.statistics_enter_result_col = col()?,
+            "statistics-leave-result-col" => self.statistics_leave_result_col = col()?,
+            "statistics-work-minutes-col" => self.statistics_work_minutes_col = col()?,
+            "statistics-start-row" => self.statistics_start_row = row()?,
+            "statistics-header-row" => self.statistics_header_row = row()?,
+            "statistics-employee-id-header" => self.statistics_employee_id_header = text(),
+            "statistics-date-header" => self.statistics_date_header = text(),
+            "statistics-enter-result-header" => self.statistics_enter_result_header = text(),
+            "statistics-leave-result-header" => self.statistics_leave_result_header = text(),
+            "statistics-work-minutes-header" => self.statistics_work_minutes_header = text(),
+            "record-employee-id-col" => self.record_employee_id_col = col()?,
+            "record-date-col" => self.record_date_col = col()?,
+            "record-abnormal-reason-col" => self.record_abnormal_reason_col = col()?,
+            "record-start-row" => self.record_start_row = row()?,
+            "record-header-row" => self.record_header_row = row()?,
+            "record-employee-id-header" => self.record_employee_id_header = text(),
+            "record-date-header" => self.record_date_header = text(),
+            "record-abnormal-reason-header" => self.record_abnormal_reason_header = text(),
+            "template" => {
+                // 多个模板sheet用分号分隔，每个为“工号列,数据起始列,表头行[,sheet名称]”，如 A,G,2;D,I,2,研发部
+                // 每日列、工时设置沿用原来同一位置的配置
+                self.template_cfg = value
+                    .split(';')
+                    .filter(|cfg| !cfg.trim().is_empty())
+                    .enumerate()
+                    .map(
+                        |(i, cfg)| match cfg.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+                            [employee_id_col, start_col, title_row, ref sheet_name @ ..]
+                                if sheet_name.len() <= 1 =>
+                            {
+                                Some(SheetConfig {
+                                    employee_id_col: parse_col(employee_id_col)?,
+                                    start_col: parse_col(start_col)?,
+                                    title_row: title_row
+                                        .parse::<u32>()
+                                        .ok()
+                                        .filter(|&row| row > 0)?,
+                                    sheet_name: sheet_name.first().unwrap_or(&"").to_string(),
+                                    ..self.template_cfg.get(i).cloned().unwrap_or_default()
+                                })
+                            }
+                            _ => None,
+                        },
+                    )
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(anyhow!("{key}，模板配置填写有误：{value}"))?;
+            }
+            "summary-columns" => self.summary_columns = flag()?,
+            "clear-block" => self.clear_block = flag()?,
+            "no-data-text" => self.no_data_text = text(),
+            "overtime" => self.overtime.enabled = flag()?,
+            "overtime-workday-hours" => self.overtime.workday_hours = hours()?,
+            "overtime-restday-hours" => self.overtime.restday_hours = hours()?,
+            "punch-employee-id-col" => self.punch_employee_id_col = col()?,
+            "punch-time-col" => self.punch_time_col = col()?,
+            "punch-device-col" => self.punch_device_col = col()?,
+            "punch-start-row" => self.punch_start_row = row()?,
+            "punch-header-row" => self.punch_header_row = row()?,
+            "punch-employee-id-header" => self.punch_employee_id_header = text(),
+            "punch-time-header" => self.punch_time_header = text(),
+            "punch-device-header" => self.punch_device_header = text(),
+            "shift-start" => self.shift.start = minutes()?,
+            "shift-end" => self.shift.end = minutes()?,
+            "lunch-start" => self.shift.lunch_start = minutes()?,
+            "lunch-end" => self.shift.lunch_end = minutes()?,
+            "style-preset" => {
+                self.style_preset = StylePreset::from_name(value).ok_or(anyhow!(
+                    "{key}，请填写{}：{value}",
+                    StylePreset::NAMES.join("或")
+                ))?
+            }
+            _ => return Err(anyhow!("未知的参数：{key}")),
+        }
+
+        Ok(())
+    }
+
+    /// 设置模板配置的参数，`index`为None时设置全部模板配置
+    fn set_template(
+        &mut self,
+        key: &str,
+        name: &str,
+        index: Option<usize>,
+        value: &str,
+    ) -> Result<()> {
+        let number = || {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|&number| number > 0.0)
+                .ok_or(anyhow!("{key}，请填写大于0的数字：{value}"))
+        };
+        let update: Box<dyn Fn(&mut SheetConfig)> = match name {
+            "day-columns" => {
+                let day_columns = parse_day_columns(value).map_err(|e| anyhow!("{key}，{e}"))?;
+                Box::new(move |cfg| cfg.day_columns = day_columns.clone())
+            }
+            "work-time-unit" => {
+                let unit = WorkTimeUnit::from_name(value).ok_or(anyhow!(
+                    "{key}，请填写{}：{value}",
+                    WorkTimeUnit::NAMES.join("、")
+                ))?;
+                Box::new(move |cfg| cfg.work_time.unit = unit)
+            }
+            "rounding" => {
+                let rounding = Rounding::from_name(value).ok_or(anyhow!(
+                    "{key}，请填写{}：{value}",
+                    Rounding::NAMES.join("、")
+                ))?;
+                Box::new(move |cfg| cfg.work_time.rounding = rounding)
+            }
+            "precision" => {
+                let precision = number()?;
+                Box::new(move |cfg| cfg.work_time.precision = precision)
+            }
+            "standard-hours" => {
+                let standard_hours = number()?;
+                Box::new(move |cfg| cfg.work_time.standard_hours = standard_hours)
+            }
+            _ => return Err(anyhow!("未知的参数：{key}")),
+        };
+
+        let configs = match index {
+            None => &mut self.template_cfg[..],
+            Some(n) => {
+                let i = n
+                    .checked_sub(1)
+                    .filter(|&i| i < self.template_cfg.len())
+                    .ok_or(anyhow!("{key}，没有第{n}个模板配置"))?;
+                &mut self.template_cfg[i..=i]
+            }
+        };
+        for cfg in configs {
+            update(cfg);
+        }
+        Ok(())
+    }
+}
+
+/// 拆出模板配置参数的名称和序号，如 day-columns-2 为 ("day-columns", Some(2))
+fn split_template_key(key: &str) -> Option<(&'static str, Option<usize>)> {
+    TEMPLATE_KEYS
+        .iter()
+        .find_map(|&name| match key.strip_prefix(name)? {
+            "" => Some((name, None)),
+            rest => Some((name, Some(rest.strip_prefix('-')?.parse().ok()?))),
+        })
+}
+
+fn get_3_alpha(ss: &str) -> String {
+    ss.chars()
+        .filter(|&c| c.is_ascii_alphabetic())
+        .take(3)
+        .map(|c| c.to_ascii_uppercase())
+        .collect::<String>()
+}
+
+/// 把“d”、“AB”之类的列字母解析为列号，忽略非字母字符
+pub fn parse_col(old_value: &str) -> Option<u32> {
+    let col_str = get_3_alpha(old_value);
+    if col_str.is_empty() {
+        None
+    } else {
+        Some(column_index_from_string(col_str))
+    }
+}
@@ -0,0 +1,198 @@
+//! 考勤结果关键词规则：打卡结果或异常原因包含某个关键词时，报表中显示对应的文字。
+//! 规则列表存在sled的`keyword_rules`键下，没有保存过时使用默认规则。
+//! 增删字段需同时升级数据库版本，见[`crate::migrate`]
+
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+
+use crate::store::Attendance;
+
+pub(crate) const KEYWORD_RULES_KEY: &str = "keyword_rules";
+
+/// 一条关键词规则
+#[derive(Debug, Clone, Default, Readable, Writable, PartialEq)]
+pub struct KeywordRule {
+    /// 要查找的关键词，多个关键词用“|”分隔，包含其中任意一个即匹配
+    pub pattern: String,
+    /// 优先级，数字小的先匹配，相同时按列表顺序
+    pub priority: u32,
+    /// 上班打卡结果匹配时显示的文字
+    pub enter_text: String,
+    /// 下班打卡结果匹配时显示的文字
+    pub leave_text: String,
+    /// 异常打卡原因匹配时显示的文字
+    pub reason_text: String,
+}
+
+impl KeywordRule {
+    fn new(
+        pattern: &str,
+        priority: u32,
+        enter_text: &str,
+        leave_text: &str,
+        reason_text: &str,
+    ) -> Self {
+        KeywordRule {
+            pattern: pattern.to_string(),
+            priority,
+            enter_text: enter_text.to_string(),
+            leave_text: leave_text.to_string(),
+            reason_text: reason_text.to_string(),
+        }
+    }
+
+    /// 第一个关键词，用作汇总列的名称
+    pub fn label(&self) -> &str {
+        self.pattern.split('|').next().unwrap_or_default().trim()
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.pattern
+            .split('|')
+            .map(str::trim)
+            .any(|keyword| !keyword.is_empty() && text.contains(keyword))
+    }
+}
+
+/// 默认规则，与早期版本写死的关键词和替换一致
+pub fn default_rules() -> Vec<KeywordRule> {
+    vec![
+        KeywordRule::new("缺卡", 1, "缺早卡", "缺晚卡", "缺卡"),
+        KeywordRule::new("补卡", 2, "补早卡", "补晚卡", "补卡"),
+        KeywordRule::new("迟到", 3, "迟到", "迟到", "迟到"),
+        KeywordRule::new("早退", 4, "早退", "早退", "早退"),
+        KeywordRule::new("虚拟", 5, "虚拟", "虚拟", "虚拟"),
+    ]
+}
+
+/// 读取保存的规则，按优先级排序；没有保存过时为默认规则
+///
+/// 保存的规则读不出来时报错，不用默认规则代替，以免之后保存时覆盖原有的规则
+pub fn load(db: &Db) -> Result<Vec<KeywordRule>> {
+    let mut rules = match db.get(KEYWORD_RULES_KEY)? {
+        Some(value) => Vec::<KeywordRule>::read_from_buffer(&value)
+            .map_err(|e| anyhow!("读取保存的关键词规则失败：{e}，请备份数据库后联系开发者"))?,
+        None => default_rules(),
+    };
+    rules.sort_by_key(|rule| rule.priority);
+    Ok(rules)
+}
+
+pub fn save(db: &Db, rules: &[KeywordRule]) -> Result<()> {
+    db.insert(KEYWORD_RULES_KEY, rules.to_vec().write_to_vec()?)?;
+    Ok(())
+}
+
+/// 一天考勤按规则得到的显示文字（上班、下班、异常原因各一行），以及显示了文字的规则在`rules`中的序号
+///
+/// 每项取第一条匹配的规则，`rules`需已按优先级排序
+pub fn summarize(rules: &[KeywordRule], attendance: &Attendance) -> (String, Vec<usize>) {
+    type Output = fn(&KeywordRule) -> &str;
+    let fields: [(&str, Output); 3] = [
+        (&attendance.enter_info, |rule| &rule.enter_text),
+        (&attendance.leave_info, |rule| &rule.leave_text),
+        (&attendance.abnormal_reason, |rule| &rule.reason_text),
+    ];
+    let mut lines = Vec::new();
+    let mut matched = Vec::new();
+    for (text, output) in fields {
+        let line = match rules.iter().position(|rule| rule.is_match(text)) {
+            Some(index) if !output(&rules[index]).is_empty() => {
+                if !matched.contains(&index) {
+                    matched.push(index);
+                }
+                output(&rules[index])
+            }
+            _ => "",
+        };
+        lines.push(line);
+    }
+    (lines.join("\n").trim().to_string(), matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 早期版本写死的关键词
+    fn sum_up(reason: &str) -> String {
+        for i in ["缺卡", "补卡", "迟到", "早退", "虚拟"] {
+            if reason.contains(i) {
+                return i.to_string();
+            }
+        }
+        String::new()
+    }
+
+    /// 早期版本报表中的考勤文字
+    fn legacy_status(attendance: &Attendance) -> String {
+        format!(
+            "{}\n{}\n{}",
+            sum_up(&attendance.enter_info)
+                .replace("缺卡", "缺早卡")
+                .replace("补卡", "补早卡"),
+            sum_up(&attendance.leave_info)
+                .replace("缺卡", "缺晚卡")
+                .replace("补卡", "补晚卡"),
+            sum_up(&attendance.abnormal_reason)
+        )
+        .trim()
+        .to_string()
+    }
+
+    #[test]
+    fn default_rules_match_legacy_output() {
+        let texts = [
+            "",
+            "正常",
+            "缺卡",
+            "补卡审批通过",
+            "迟到12分钟",
+            "早退3分钟",
+            "虚拟打卡",
+            "迟到5分钟，补卡",
+            "缺卡，早退",
+            "请假",
+        ];
+        let rules = default_rules();
+        for enter_info in texts {
+            for leave_info in texts {
+                for abnormal_reason in texts {
+                    let attendance = Attendance {
+                        enter_info: enter_info.to_string(),
+                        leave_info: leave_info.to_string(),
+                        abnormal_reason: abnormal_reason.to_string(),
+                        ..Default::default()
+                    };
+                    assert_eq!(
+                        summarize(&rules, &attendance).0,
+                        legacy_status(&attendance),
+                        "{attendance:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn replacements_depend_on_field() {
+        let attendance = Attendance {
+            enter_info: "缺卡".to_string(),
+            leave_info: "补卡".to_string(),
+            abnormal_reason: "缺卡".to_string(),
+            ..Default::default()
+        };
+        let (status, matched) = summarize(&default_rules(), &attendance);
+        assert_eq!(status, "缺早卡\n补晚卡\n缺卡");
+        assert_eq!(matched, vec![0, 1]);
+    }
+
+    #[test]
+    fn unreadable_rules_are_an_error() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        assert_eq!(load(&db).unwrap(), default_rules());
+        db.insert(KEYWORD_RULES_KEY, b"garbage".as_slice()).unwrap();
+        assert!(load(&db).is_err());
+    }
+}
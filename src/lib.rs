@@ -0,0 +1,15 @@
+//! 考勤助手核心逻辑，不依赖界面：
+//! 导入每日统计表、原始记录表和打卡记录到sled，再按模板生成考勤报表。
+
+pub mod calendar;
+pub mod date;
+pub mod highlight;
+pub mod import;
+pub mod input;
+pub mod keyword;
+pub mod migrate;
+pub mod overtime;
+pub mod punch;
+pub mod report;
+pub mod store;
+pub mod table;
@@ -1,11 +1,45 @@
-#![windows_subsystem = "windows"]
+// 界面程序在Windows上不弹出控制台窗口，命令行模式再连接到启动它的控制台
+#![cfg_attr(feature = "gui", windows_subsystem = "windows")]
 use anyhow::Result;
 
+#[cfg(feature = "gui")]
 slint::include_modules!();
 
-mod attendance;
+#[cfg(feature = "gui")]
+mod app;
+mod cli;
 
 fn main() -> Result<()> {
-    let app = attendance::App::new();
-    app.run()
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        attach_console();
+    }
+    let db_path = cli::take_db_path(&mut args)?;
+    #[cfg(feature = "gui")]
+    if args.is_empty() {
+        // 界面程序没有控制台，启动失败时弹窗提示
+        return liando::store::open(&db_path)
+            .and_then(app::App::new)
+            .and_then(|app| app.run())
+            .inspect_err(app::show_error);
+    }
+
+    cli::run(args, &db_path)
+}
+
+/// 从cmd或批处理运行命令时，把输出和错误信息写到启动它的控制台
+#[cfg(all(windows, feature = "gui"))]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // 没有父控制台（如双击启动）时失败，忽略即可
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
 }
+
+#[cfg(not(all(windows, feature = "gui")))]
+fn attach_console() {}
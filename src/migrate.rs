@@ -0,0 +1,958 @@
+//! 数据库结构升级。
+//!
+//! speedy按字段顺序编码，`UserInput`、`Attendance`等存储结构一旦增删字段，旧数据就读不出来。
+//! 所以每次改动存储结构都要把[`SCHEMA_VERSION`]加一，在这里冻结旧版本的结构定义，
+//! 并在`upgrade`中补上从上一版本升级的步骤。`import_history`里的`Changeset`也包含`Attendance`，
+//! `Attendance`改动时要一并升级，写入`history`这个batch。`keyword_rules`、`highlight_rules`下的
+//! 规则列表同样按字段顺序编码，改动`KeywordRule`、`HighlightRule`时也要在这里冻结旧结构并升级。
+//!
+//! 冻结的结构只能由本模块中的类型组成，不能引用`SheetConfig`、`StylePreset`等还会变化的类型，
+//! 否则它们改动后，旧版本的数据就会按新结构解码。speedy编码结构体时直接拼接各字段，
+//! 所以只在末尾加字段的版本可以把上一版本嵌套为第一个字段，如`UserInputV9 { v8, punch }`，
+//! 编码与展开写完全相同，不用逐个字段照抄。
+//!
+//! 数据读不出来时报错并停止升级，不跳过，以免之后按默认值保存时覆盖用户原有的数据。
+
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use sled::transaction::{ConflictableTransactionError, Transactional};
+use sled::{Batch, Db};
+use speedy::{LittleEndian, Readable, Writable};
+
+use crate::highlight::HIGHLIGHT_RULES_KEY;
+use crate::import::HISTORY_TREE;
+use crate::input::USER_INPUT_KEY;
+use crate::keyword::KEYWORD_RULES_KEY;
+
+/// 当前的数据库结构版本
+pub const SCHEMA_VERSION: u32 = 11;
+
+const VERSION_KEY: &str = "schema_version";
+
+/// 把数据库升级到当前版本，每个版本的升级在一个事务中完成
+pub(crate) fn run(db: &Db) -> Result<()> {
+    let mut version = match db.get(VERSION_KEY)? {
+        Some(value) => u32::from_be_bytes(
+            value
+                .as_ref()
+                .try_into()
+                .map_err(|_| anyhow!("数据库版本号有误"))?,
+        ),
+        // 新建的数据库直接是当前版本
+        None if db.is_empty() => SCHEMA_VERSION,
+        // 0.2.2及以前没有记录版本号
+        None => 0,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "数据库版本为{version}，当前程序只支持到{SCHEMA_VERSION}，请使用新版考勤助手"
+        ));
+    }
+
+    let history_tree = db.open_tree(HISTORY_TREE)?;
+    while version < SCHEMA_VERSION {
+        let mut batch = Batch::default();
+        let mut history = Batch::default();
+        upgrade(version, db, &mut batch, &mut history)?;
+        version += 1;
+        batch.insert(VERSION_KEY, &version.to_be_bytes());
+        (&**db, &history_tree)
+            .transaction(|(data, history_tree)| {
+                data.apply_batch(&batch)?;
+                history_tree.apply_batch(&history)?;
+                Ok::<_, ConflictableTransactionError>(())
+            })
+            .map_err(|e| anyhow!("升级数据库失败：{e}"))?;
+    }
+
+    if db.get(VERSION_KEY)?.is_none() {
+        db.insert(VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())?;
+    }
+    Ok(())
+}
+
+/// 从`version`升级到下一版本
+fn upgrade(version: u32, db: &Db, batch: &mut Batch, history: &mut Batch) -> Result<()> {
+    match version {
+        // 0.2.2：还没有表头名称相关的字段
+        0 => upgrade_user_input(db, batch, version, |old: UserInputV0| UserInputV1 {
+            base: InputBase {
+                start_date: old.start_date,
+                end_date: old.end_date,
+                statistics_employee_id_col: old.statistics_employee_id_col,
+                statistics_date_col: old.statistics_date_col,
+                statistics_enter_result_col: old.statistics_enter_result_col,
+                statistics_leave_result_col: old.statistics_leave_result_col,
+                statistics_work_minutes_col: old.statistics_work_minutes_col,
+                statistics_start_row: old.statistics_start_row,
+                statistics_header_row: 4,
+                statistics_employee_id_header: String::new(),
+                statistics_date_header: String::new(),
+                statistics_enter_result_header: String::new(),
+                statistics_leave_result_header: String::new(),
+                statistics_work_minutes_header: String::new(),
+                record_employee_id_col: old.record_employee_id_col,
+                record_date_col: old.record_date_col,
+                record_abnormal_reason_col: old.record_abnormal_reason_col,
+                record_start_row: old.record_start_row,
+                record_header_row: 3,
+                record_employee_id_header: String::new(),
+                record_date_header: String::new(),
+                record_abnormal_reason_header: String::new(),
+            },
+            template_cfg: old.template_cfg,
+        }),
+        // 还没有汇总列开关
+        1 => upgrade_user_input(db, batch, version, |v1| UserInputV2 {
+            v1,
+            summary_columns: false,
+        }),
+        // 还没有样式预设
+        2 => upgrade_user_input(db, batch, version, |v2| UserInputV3 {
+            v2,
+            style_preset: StylePresetV3::Compact,
+        }),
+        // 还没有清空数据区域和无数据标记
+        3 => upgrade_user_input(db, batch, version, |old: UserInputV3| UserInputV4 {
+            base: old.v2.v1.base,
+            template_cfg: old.v2.v1.template_cfg,
+            options: OptionsV4 {
+                summary_columns: old.v2.summary_columns,
+                style_preset: old.style_preset,
+                clear_block: false,
+                no_data_text: "无数据".to_string(),
+            },
+        }),
+        // 模板配置还是（工号列，数据起始列，表头行），没有sheet名称
+        4 => upgrade_user_input(db, batch, version, |old: UserInputV4| UserInputV5 {
+            base: old.base,
+            template_cfg: old
+                .template_cfg
+                .into_iter()
+                .map(|(employee_id_col, start_col, title_row)| SheetConfigV5 {
+                    employee_id_col,
+                    start_col,
+                    title_row,
+                    sheet_name: String::new(),
+                })
+                .collect(),
+            options: old.options,
+        }),
+        // 模板配置没有每日列，固定为工时、考勤两列
+        5 => upgrade_user_input(db, batch, version, |old: UserInputV5| {
+            UserInputV6 {
+            base: old.base,
+            template_cfg: old
+                .template_cfg
+                .into_iter()
+                .map(|v5| SheetConfigV6 {
+                    v5,
+                    day_columns: vec![
+                        DayColumnV6 {
+                            field: DayFieldV6::Hours,
+                            header: "{date}个人投入度".to_string(),
+                            width: 0.0,
+                        },
+                        DayColumnV6 {
+                            field: DayFieldV6::Status,
+                            header: "{date}考勤\n（正常/不正常（缺卡、补卡、虚拟打卡、非主责项目或城市打卡），不正常说明原因）".to_string(),
+                            width: 15.0,
+                        },
+                    ],
+                })
+                .collect(),
+            options: old.options,
+        }
+        }),
+        // 模板配置没有工时单位和取整设置，固定为小时、不取整
+        6 => upgrade_user_input(db, batch, version, |old: UserInputV6| UserInputV7 {
+            base: old.base,
+            template_cfg: old
+                .template_cfg
+                .into_iter()
+                .map(|v6| SheetConfigV7 {
+                    v6,
+                    work_time: WorkTimeV7 {
+                        unit: WorkTimeUnitV7::Hours,
+                        rounding: RoundingV7::Off,
+                        precision: 0.01,
+                        standard_hours: 8.0,
+                    },
+                })
+                .collect(),
+            options: old.options,
+        }),
+        // 还没有加班统计的设置
+        7 => upgrade_user_input(db, batch, version, |v7| UserInputV8 {
+            v7,
+            overtime: OvertimeRuleV8 {
+                enabled: false,
+                workday_hours: 8.0,
+                restday_hours: 0.0,
+            },
+        }),
+        // 还没有打卡记录表和班次的设置，`Attendance`还没有首次、末次打卡时间
+        8 => {
+            upgrade_user_input(db, batch, version, |v8| UserInputV9 {
+                v8,
+                punch: PunchInputV9 {
+                    employee_id_col: 1,
+                    time_col: 2,
+                    device_col: 3,
+                    start_row: 2,
+                    header_row: 1,
+                    employee_id_header: String::new(),
+                    time_header: String::new(),
+                    device_header: String::new(),
+                    shift: ShiftV9 {
+                        start: 9 * 60,
+                        end: 18 * 60,
+                        lunch_start: 12 * 60,
+                        lunch_end: 13 * 60,
+                    },
+                },
+            })?;
+
+            upgrade_attendance(db, batch, |v1: AttendanceV1| AttendanceV9::from(v1))?;
+            for kv in db.open_tree(HISTORY_TREE)?.iter() {
+                let (id, value) = kv?;
+                let old = decode::<ChangesetV1>(&value, "导入历史")?;
+                let changeset = ChangesetV9 {
+                    source: old.source,
+                    imported_at: old.imported_at,
+                    previous: old
+                        .previous
+                        .into_iter()
+                        .map(|(key, attendance)| (key, attendance.map(AttendanceV9::from)))
+                        .collect(),
+                    added_punches: Vec::new(),
+                };
+                history.insert(id, changeset.write_to_vec()?);
+            }
+            Ok(())
+        }
+        // `Attendance`还没有记录上下班打卡结果和工作时长是否由打卡算出。有首次或末次打卡的
+        // 工作时长是打卡算出的；上下班打卡结果可能已被每日统计表覆盖，按不是打卡算出的处理，
+        // 以免再导入打卡时覆盖请假、外勤等结果
+        9 => {
+            upgrade_attendance(db, batch, |v9: AttendanceV9| AttendanceV10::from(v9))?;
+            for kv in db.open_tree(HISTORY_TREE)?.iter() {
+                let (id, value) = kv?;
+                let old = decode::<ChangesetV9>(&value, "导入历史")?;
+                let changeset = ChangesetV10 {
+                    source: old.source,
+                    imported_at: old.imported_at,
+                    previous: old
+                        .previous
+                        .into_iter()
+                        .map(|(key, attendance)| (key, attendance.map(AttendanceV10::from)))
+                        .collect(),
+                    added_punches: old.added_punches,
+                };
+                history.insert(id, changeset.write_to_vec()?);
+            }
+            Ok(())
+        }
+        // 关键词规则和高亮规则从这一版起纳入版本管理，结构没变，按冻结的结构检查一遍，
+        // 读不出来时停止升级
+        10 => {
+            upgrade_value(
+                db,
+                batch,
+                KEYWORD_RULES_KEY,
+                "关键词规则",
+                |rules: Vec<KeywordRuleV1>| rules,
+            )?;
+            upgrade_value(
+                db,
+                batch,
+                HIGHLIGHT_RULES_KEY,
+                "高亮规则",
+                |rules: Vec<HighlightRuleV2>| rules,
+            )
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// 升级全部考勤记录，键为“{日期}_{工号}”，以数字开头，其他键都是英文名称
+fn upgrade_attendance<Old, New>(
+    db: &Db,
+    batch: &mut Batch,
+    upgrade: impl Fn(Old) -> New,
+) -> Result<()>
+where
+    Old: for<'a> Readable<'a, LittleEndian>,
+    New: Writable<LittleEndian>,
+{
+    for kv in db.iter() {
+        let (key, value) = kv?;
+        if !key.first().is_some_and(u8::is_ascii_digit) {
+            continue;
+        }
+        let old = decode::<Old>(
+            &value,
+            format_args!("考勤记录{}", String::from_utf8_lossy(&key)),
+        )?;
+        batch.insert(key, upgrade(old).write_to_vec()?);
+    }
+    Ok(())
+}
+
+/// 升级保存的输入
+fn upgrade_user_input<Old, New>(
+    db: &Db,
+    batch: &mut Batch,
+    version: u32,
+    upgrade: impl FnOnce(Old) -> New,
+) -> Result<()>
+where
+    Old: for<'a> Readable<'a, LittleEndian>,
+    New: Writable<LittleEndian>,
+{
+    upgrade_value(
+        db,
+        batch,
+        USER_INPUT_KEY,
+        format_args!("版本{version}的输入"),
+        upgrade,
+    )
+}
+
+/// 升级某个键下保存的值；从没保存过时没有这个键，不用升级
+fn upgrade_value<Old, New>(
+    db: &Db,
+    batch: &mut Batch,
+    key: &str,
+    what: impl fmt::Display,
+    upgrade: impl FnOnce(Old) -> New,
+) -> Result<()>
+where
+    Old: for<'a> Readable<'a, LittleEndian>,
+    New: Writable<LittleEndian>,
+{
+    let Some(value) = db.get(key)? else {
+        return Ok(());
+    };
+    let old = decode::<Old>(&value, what)?;
+    batch.insert(key, upgrade(old).write_to_vec()?);
+    Ok(())
+}
+
+/// 按旧版本的结构解码，须正好用完全部字节
+fn decode<T>(value: &[u8], what: impl fmt::Display) -> Result<T>
+where
+    T: for<'a> Readable<'a, LittleEndian>,
+{
+    match T::read_with_length_from_buffer(value) {
+        (Ok(old), length) if length == value.len() => Ok(old),
+        _ => Err(anyhow!(
+            "数据库中的{what}无法读取，已停止升级，以免覆盖原有数据，请备份数据库后联系开发者"
+        )),
+    }
+}
+
+/// 版本0（0.2.2）的输入
+#[derive(Readable, Writable)]
+struct UserInputV0 {
+    start_date: i32,
+    end_date: i32,
+    statistics_employee_id_col: u32,
+    statistics_date_col: u32,
+    statistics_enter_result_col: u32,
+    statistics_leave_result_col: u32,
+    statistics_work_minutes_col: u32,
+    statistics_start_row: u32,
+    record_employee_id_col: u32,
+    record_date_col: u32,
+    record_abnormal_reason_col: u32,
+    record_start_row: u32,
+    template_cfg: Vec<(u32, u32, u32)>,
+}
+
+/// 版本1起不变的开头部分：日期和两个导入表的设置
+#[derive(Readable, Writable)]
+struct InputBase {
+    start_date: i32,
+    end_date: i32,
+    statistics_employee_id_col: u32,
+    statistics_date_col: u32,
+    statistics_enter_result_col: u32,
+    statistics_leave_result_col: u32,
+    statistics_work_minutes_col: u32,
+    statistics_start_row: u32,
+    statistics_header_row: u32,
+    statistics_employee_id_header: String,
+    statistics_date_header: String,
+    statistics_enter_result_header: String,
+    statistics_leave_result_header: String,
+    statistics_work_minutes_header: String,
+    record_employee_id_col: u32,
+    record_date_col: u32,
+    record_abnormal_reason_col: u32,
+    record_start_row: u32,
+    record_header_row: u32,
+    record_employee_id_header: String,
+    record_date_header: String,
+    record_abnormal_reason_header: String,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV1 {
+    base: InputBase,
+    template_cfg: Vec<(u32, u32, u32)>,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV2 {
+    v1: UserInputV1,
+    summary_columns: bool,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV3 {
+    v2: UserInputV2,
+    style_preset: StylePresetV3,
+}
+
+#[derive(Readable, Writable)]
+enum StylePresetV3 {
+    Compact,
+    Template,
+}
+
+/// 版本4起模板配置之后的报表选项
+#[derive(Readable, Writable)]
+struct OptionsV4 {
+    summary_columns: bool,
+    style_preset: StylePresetV3,
+    clear_block: bool,
+    no_data_text: String,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV4 {
+    base: InputBase,
+    template_cfg: Vec<(u32, u32, u32)>,
+    options: OptionsV4,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV5 {
+    base: InputBase,
+    template_cfg: Vec<SheetConfigV5>,
+    options: OptionsV4,
+}
+
+#[derive(Readable, Writable)]
+struct SheetConfigV5 {
+    employee_id_col: u32,
+    start_col: u32,
+    title_row: u32,
+    sheet_name: String,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV6 {
+    base: InputBase,
+    template_cfg: Vec<SheetConfigV6>,
+    options: OptionsV4,
+}
+
+#[derive(Readable, Writable)]
+struct SheetConfigV6 {
+    v5: SheetConfigV5,
+    day_columns: Vec<DayColumnV6>,
+}
+
+#[derive(Readable, Writable)]
+struct DayColumnV6 {
+    field: DayFieldV6,
+    header: String,
+    width: f64,
+}
+
+/// 每日列的内容，只在末尾加过变体，加变体不影响已有数据，不用升级
+#[derive(Readable, Writable)]
+enum DayFieldV6 {
+    Hours,
+    Status,
+    Reason,
+    EnterInfo,
+    LeaveInfo,
+    Overtime,
+    Shortfall,
+    FirstIn,
+    LastOut,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV7 {
+    base: InputBase,
+    template_cfg: Vec<SheetConfigV7>,
+    options: OptionsV4,
+}
+
+#[derive(Readable, Writable)]
+struct SheetConfigV7 {
+    v6: SheetConfigV6,
+    work_time: WorkTimeV7,
+}
+
+#[derive(Readable, Writable)]
+struct WorkTimeV7 {
+    unit: WorkTimeUnitV7,
+    rounding: RoundingV7,
+    precision: f64,
+    standard_hours: f64,
+}
+
+#[derive(Readable, Writable)]
+enum WorkTimeUnitV7 {
+    Minutes,
+    Hours,
+    Days,
+}
+
+#[derive(Readable, Writable)]
+enum RoundingV7 {
+    Off,
+    Nearest,
+    Down,
+    Up,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV8 {
+    v7: UserInputV7,
+    overtime: OvertimeRuleV8,
+}
+
+#[derive(Readable, Writable)]
+struct OvertimeRuleV8 {
+    enabled: bool,
+    workday_hours: f64,
+    restday_hours: f64,
+}
+
+#[derive(Readable, Writable)]
+struct UserInputV9 {
+    v8: UserInputV8,
+    punch: PunchInputV9,
+}
+
+/// 打卡记录表的设置和班次
+#[derive(Readable, Writable)]
+struct PunchInputV9 {
+    employee_id_col: u32,
+    time_col: u32,
+    device_col: u32,
+    start_row: u32,
+    header_row: u32,
+    employee_id_header: String,
+    time_header: String,
+    device_header: String,
+    shift: ShiftV9,
+}
+
+#[derive(Readable, Writable)]
+struct ShiftV9 {
+    start: u32,
+    end: u32,
+    lunch_start: u32,
+    lunch_end: u32,
+}
+
+/// 版本1到8的考勤记录
+#[derive(Readable, Writable)]
+struct AttendanceV1 {
+    employee_id: String,
+    enter_info: String,
+    leave_info: String,
+    work_minutes: f64,
+    abnormal_reason: String,
+}
+
+#[derive(Readable, Writable)]
+struct AttendanceV9 {
+    v1: AttendanceV1,
+    first_in: String,
+    last_out: String,
+}
+
+impl From<AttendanceV1> for AttendanceV9 {
+    fn from(v1: AttendanceV1) -> Self {
+        AttendanceV9 {
+            v1,
+            first_in: String::new(),
+            last_out: String::new(),
+        }
+    }
+}
+
+#[derive(Readable, Writable)]
+struct AttendanceV10 {
+    v9: AttendanceV9,
+    results_from_punches: bool,
+    minutes_from_punches: bool,
+}
+
+impl From<AttendanceV9> for AttendanceV10 {
+    fn from(v9: AttendanceV9) -> Self {
+        AttendanceV10 {
+            minutes_from_punches: !v9.first_in.is_empty() || !v9.last_out.is_empty(),
+            results_from_punches: false,
+            v9,
+        }
+    }
+}
+
+/// 版本1起的关键词规则，`keyword_rules`键下保存的是它的列表
+#[derive(Readable, Writable)]
+struct KeywordRuleV1 {
+    pattern: String,
+    priority: u32,
+    enter_text: String,
+    leave_text: String,
+    reason_text: String,
+}
+
+/// 版本2起的高亮规则，`highlight_rules`键下保存的是它的列表
+#[derive(Readable, Writable)]
+struct HighlightRuleV2 {
+    pattern: String,
+    hours_below: f64,
+    fill_color: String,
+    font_color: String,
+}
+
+#[derive(Readable, Writable)]
+struct ChangesetV1 {
+    source: String,
+    imported_at: i64,
+    previous: Vec<(String, Option<AttendanceV1>)>,
+}
+
+#[derive(Readable, Writable)]
+struct ChangesetV9 {
+    source: String,
+    imported_at: i64,
+    previous: Vec<(String, Option<AttendanceV9>)>,
+    added_punches: Vec<String>,
+}
+
+#[derive(Readable, Writable)]
+struct ChangesetV10 {
+    source: String,
+    imported_at: i64,
+    previous: Vec<(String, Option<AttendanceV10>)>,
+    added_punches: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight;
+    use crate::import::Changeset;
+    use crate::input::UserInput;
+    use crate::keyword::{self, KeywordRule};
+    use crate::overtime::OvertimeRule;
+    use crate::punch::Shift;
+    use crate::report::{default_day_columns, DayColumn, DayField, StylePreset, WorkTime};
+    use crate::store::Attendance;
+
+    fn temporary_db() -> Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    fn version(db: &Db) -> u32 {
+        u32::from_be_bytes(
+            db.get(VERSION_KEY)
+                .unwrap()
+                .unwrap()
+                .as_ref()
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    fn attendance_v1() -> AttendanceV1 {
+        AttendanceV1 {
+            employee_id: "1001".to_string(),
+            enter_info: "正常".to_string(),
+            leave_info: "缺卡".to_string(),
+            work_minutes: 480.0,
+            abnormal_reason: "忘记打卡".to_string(),
+        }
+    }
+
+    fn attendance() -> Attendance {
+        Attendance {
+            employee_id: "1001".to_string(),
+            enter_info: "正常".to_string(),
+            leave_info: "缺卡".to_string(),
+            work_minutes: 480.0,
+            abnormal_reason: "忘记打卡".to_string(),
+            first_in: String::new(),
+            last_out: String::new(),
+            results_from_punches: false,
+            minutes_from_punches: false,
+        }
+    }
+
+    #[test]
+    fn upgrades_baseline_data_to_current_version() {
+        let db = temporary_db();
+        let user_input = UserInputV0 {
+            start_date: 2460073,
+            end_date: 2460077,
+            statistics_employee_id_col: 5,
+            statistics_date_col: 8,
+            statistics_enter_result_col: 11,
+            statistics_leave_result_col: 13,
+            statistics_work_minutes_col: 21,
+            statistics_start_row: 6,
+            record_employee_id_col: 3,
+            record_date_col: 6,
+            record_abnormal_reason_col: 12,
+            record_start_row: 5,
+            template_cfg: vec![(1, 7, 2), (4, 9, 3)],
+        };
+        db.insert(USER_INPUT_KEY, user_input.write_to_vec().unwrap())
+            .unwrap();
+        db.insert("2023-05-08_1001", attendance_v1().write_to_vec().unwrap())
+            .unwrap();
+        let changeset = ChangesetV1 {
+            source: "statistics.xlsx".to_string(),
+            imported_at: 1683500000,
+            previous: vec![
+                ("2023-05-08_1001".to_string(), Some(attendance_v1())),
+                ("2023-05-09_1001".to_string(), None),
+            ],
+        };
+        let history = db.open_tree(HISTORY_TREE).unwrap();
+        history
+            .insert(1u64.to_be_bytes(), changeset.write_to_vec().unwrap())
+            .unwrap();
+
+        run(&db).unwrap();
+        assert_eq!(version(&db), SCHEMA_VERSION);
+
+        let user_input = UserInput::load(&db).unwrap();
+        assert_eq!(
+            (user_input.start_date, user_input.end_date),
+            (2460073, 2460077)
+        );
+        assert_eq!(user_input.statistics_employee_id_col, 5);
+        assert_eq!(user_input.statistics_work_minutes_col, 21);
+        assert_eq!(user_input.statistics_start_row, 6);
+        assert_eq!(user_input.statistics_header_row, 4);
+        assert_eq!(user_input.statistics_date_header, "");
+        assert_eq!(user_input.record_abnormal_reason_col, 12);
+        assert_eq!(user_input.record_start_row, 5);
+        assert_eq!(user_input.record_header_row, 3);
+        assert_eq!(user_input.template_cfg.len(), 2);
+        let cfg = &user_input.template_cfg[1];
+        assert_eq!(
+            (cfg.employee_id_col, cfg.start_col, cfg.title_row),
+            (4, 9, 3)
+        );
+        assert_eq!(cfg.sheet_name, "");
+        assert_eq!(cfg.day_columns, default_day_columns());
+        assert_eq!(cfg.work_time, WorkTime::default());
+        assert!(!user_input.summary_columns);
+        assert_eq!(user_input.style_preset, StylePreset::Compact);
+        assert!(!user_input.clear_block);
+        assert_eq!(user_input.no_data_text, "无数据");
+        assert_eq!(user_input.overtime, OvertimeRule::default());
+        assert_eq!(
+            (
+                user_input.punch_employee_id_col,
+                user_input.punch_time_col,
+                user_input.punch_device_col,
+                user_input.punch_start_row,
+                user_input.punch_header_row
+            ),
+            (1, 2, 3, 2, 1)
+        );
+        assert_eq!(user_input.shift, Shift::default());
+
+        let value = db.get("2023-05-08_1001").unwrap().unwrap();
+        assert_eq!(Attendance::read_from_buffer(&value).unwrap(), attendance());
+
+        let value = history.get(1u64.to_be_bytes()).unwrap().unwrap();
+        let changeset = Changeset::read_from_buffer(&value).unwrap();
+        assert_eq!(changeset.source, "statistics.xlsx");
+        assert_eq!(changeset.imported_at, 1683500000);
+        assert_eq!(
+            changeset.previous,
+            vec![
+                ("2023-05-08_1001".to_string(), Some(attendance())),
+                ("2023-05-09_1001".to_string(), None),
+            ]
+        );
+        assert!(changeset.added_punches.is_empty());
+
+        // 已是当前版本时不再改动
+        run(&db).unwrap();
+        assert_eq!(UserInput::load(&db).unwrap(), user_input);
+    }
+
+    #[test]
+    fn current_user_input_matches_latest_frozen_layout() {
+        let mut user_input = UserInput::default();
+        user_input.template_cfg[0].day_columns = DayField::ALL
+            .iter()
+            .map(|&field| DayColumn::new(field, 8.0))
+            .collect();
+        let value = user_input.write_to_vec().unwrap();
+        assert!(decode::<UserInputV9>(&value, "输入").is_ok());
+    }
+
+    #[test]
+    fn current_attendance_matches_latest_frozen_layout() {
+        let value = attendance().write_to_vec().unwrap();
+        assert!(decode::<AttendanceV10>(&value, "考勤记录").is_ok());
+        let changeset = Changeset {
+            source: "punches.csv".to_string(),
+            imported_at: 1683500000,
+            previous: vec![("2023-05-08_1001".to_string(), Some(attendance()))],
+            added_punches: vec!["2023-05-08_1001_08:55:12".to_string()],
+        };
+        let value = changeset.write_to_vec().unwrap();
+        assert!(decode::<ChangesetV10>(&value, "导入历史").is_ok());
+    }
+
+    #[test]
+    fn punch_times_mark_minutes_as_from_punches() {
+        let db = temporary_db();
+        db.insert(VERSION_KEY, &9u32.to_be_bytes()).unwrap();
+        let punched = AttendanceV9 {
+            v1: attendance_v1(),
+            first_in: "08:55".to_string(),
+            last_out: "18:10".to_string(),
+        };
+        db.insert("2023-05-08_1001", punched.write_to_vec().unwrap())
+            .unwrap();
+        db.insert(
+            "2023-05-09_1001",
+            AttendanceV9::from(attendance_v1()).write_to_vec().unwrap(),
+        )
+        .unwrap();
+
+        run(&db).unwrap();
+        let value = db.get("2023-05-08_1001").unwrap().unwrap();
+        let attendance = Attendance::read_from_buffer(&value).unwrap();
+        assert_eq!(attendance.first_in, "08:55");
+        assert!(attendance.minutes_from_punches);
+        assert!(!attendance.results_from_punches);
+        let value = db.get("2023-05-09_1001").unwrap().unwrap();
+        let attendance = Attendance::read_from_buffer(&value).unwrap();
+        assert!(!attendance.minutes_from_punches);
+    }
+
+    #[test]
+    fn current_keyword_rules_match_latest_frozen_layout() {
+        let value = keyword::default_rules().write_to_vec().unwrap();
+        assert!(decode::<Vec<KeywordRuleV1>>(&value, "关键词规则").is_ok());
+    }
+
+    #[test]
+    fn current_highlight_rules_match_latest_frozen_layout() {
+        let value = highlight::default_rules().write_to_vec().unwrap();
+        assert!(decode::<Vec<HighlightRuleV2>>(&value, "高亮规则").is_ok());
+    }
+
+    #[test]
+    fn saved_highlight_rules_survive_upgrade() {
+        let db = temporary_db();
+        db.insert(VERSION_KEY, &2u32.to_be_bytes()).unwrap();
+        let rules = vec![HighlightRuleV2 {
+            pattern: "旷工".to_string(),
+            hours_below: 4.0,
+            fill_color: "FF0000".to_string(),
+            font_color: String::new(),
+        }];
+        db.insert(HIGHLIGHT_RULES_KEY, rules.write_to_vec().unwrap())
+            .unwrap();
+
+        run(&db).unwrap();
+        let rules = highlight::load(&db).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(
+            (
+                rules[0].pattern.as_str(),
+                rules[0].hours_below,
+                rules[0].fill_color.as_str()
+            ),
+            ("旷工", 4.0, "FF0000")
+        );
+
+        db.insert(VERSION_KEY, &10u32.to_be_bytes()).unwrap();
+        db.insert(HIGHLIGHT_RULES_KEY, b"garbage".as_slice())
+            .unwrap();
+        assert!(run(&db).is_err());
+        assert!(highlight::load(&db).is_err());
+    }
+
+    #[test]
+    fn saved_keyword_rules_survive_upgrade() {
+        let db = temporary_db();
+        db.insert(VERSION_KEY, &1u32.to_be_bytes()).unwrap();
+        let rules = vec![KeywordRuleV1 {
+            pattern: "外勤|出差".to_string(),
+            priority: 1,
+            enter_text: "外勤".to_string(),
+            leave_text: "外勤".to_string(),
+            reason_text: String::new(),
+        }];
+        db.insert(KEYWORD_RULES_KEY, rules.write_to_vec().unwrap())
+            .unwrap();
+
+        run(&db).unwrap();
+        assert_eq!(version(&db), SCHEMA_VERSION);
+        assert_eq!(
+            keyword::load(&db).unwrap(),
+            vec![KeywordRule {
+                pattern: "外勤|出差".to_string(),
+                priority: 1,
+                enter_text: "外勤".to_string(),
+                leave_text: "外勤".to_string(),
+                reason_text: String::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn unreadable_keyword_rules_stop_upgrade() {
+        let db = temporary_db();
+        db.insert(VERSION_KEY, &10u32.to_be_bytes()).unwrap();
+        db.insert(KEYWORD_RULES_KEY, b"garbage".as_slice()).unwrap();
+        assert!(run(&db).is_err());
+        assert_eq!(version(&db), 10);
+    }
+
+    #[test]
+    fn unreadable_input_stops_upgrade() {
+        let db = temporary_db();
+        db.insert(VERSION_KEY, &7u32.to_be_bytes()).unwrap();
+        db.insert(USER_INPUT_KEY, b"garbage".as_slice()).unwrap();
+
+        assert!(run(&db).is_err());
+        assert_eq!(version(&db), 7);
+        assert_eq!(
+            db.get(USER_INPUT_KEY).unwrap().unwrap(),
+            b"garbage".as_slice()
+        );
+        assert!(UserInput::load(&db).is_err());
+    }
+
+    #[test]
+    fn new_database_starts_at_current_version() {
+        let db = temporary_db();
+        run(&db).unwrap();
+        assert_eq!(version(&db), SCHEMA_VERSION);
+        assert_eq!(UserInput::load(&db).unwrap(), UserInput::default());
+    }
+}
@@ -0,0 +1,213 @@
+//! 加班和欠时：按工作日、休息日各自的标准工时，计算每天比标准多出或不足的时间，
+//! 并按员工汇总日期范围内的合计。休息日包括周末和工作日历中的节假日。
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use sled::Db;
+use speedy::{Readable, Writable};
+use time::{Date, Duration};
+
+use crate::calendar;
+use crate::store::Attendance;
+
+/// 加班统计的设置
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct OvertimeRule {
+    /// 生成报表时添加加班统计sheet，汇总列中加上加班、欠时合计
+    pub enabled: bool,
+    /// 工作日的标准工时（小时）
+    pub workday_hours: f64,
+    /// 休息日的标准工时（小时），通常为0，即休息日的工时都算加班
+    pub restday_hours: f64,
+}
+
+impl Default for OvertimeRule {
+    fn default() -> Self {
+        OvertimeRule {
+            enabled: false,
+            workday_hours: 8.0,
+            restday_hours: 0.0,
+        }
+    }
+}
+
+/// 一天比标准多出或不足的分钟数，两者至多一个不为0
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DayOvertime {
+    pub overtime: f64,
+    pub shortfall: f64,
+}
+
+impl OvertimeRule {
+    /// 按某天是否上班取标准工时计算
+    pub fn day(&self, work_minutes: f64, workday: bool) -> DayOvertime {
+        let standard = if workday {
+            self.workday_hours
+        } else {
+            self.restday_hours
+        } * 60.0;
+        DayOvertime {
+            overtime: (work_minutes - standard).max(0.0),
+            shortfall: (standard - work_minutes).max(0.0),
+        }
+    }
+}
+
+/// 一个员工在日期范围内的加班合计，时间为分钟数
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OvertimeTotals {
+    pub workday_overtime: f64,
+    pub restday_overtime: f64,
+    pub shortfall: f64,
+    /// 有加班的天数
+    pub overtime_days: u32,
+    /// 有欠时的天数
+    pub shortfall_days: u32,
+}
+
+impl OvertimeTotals {
+    pub fn overtime(&self) -> f64 {
+        self.workday_overtime + self.restday_overtime
+    }
+}
+
+/// 两个日期之间（含首尾）每个有考勤记录的员工的加班合计，以工号为键
+pub fn totals(
+    db: &Db,
+    rule: &OvertimeRule,
+    start: Date,
+    end: Date,
+) -> Result<BTreeMap<String, OvertimeTotals>> {
+    let mut totals = BTreeMap::<String, OvertimeTotals>::new();
+    let mut date = start;
+    while date <= end {
+        let workday = calendar::is_workday(db, date)?;
+        for kv in db.scan_prefix(format!("{date}_")) {
+            let (_, value) = kv?;
+            let Ok(attendance) = Attendance::read_from_buffer(&value) else {
+                continue;
+            };
+            let day = rule.day(attendance.work_minutes, workday);
+            let total = totals.entry(attendance.employee_id).or_default();
+            if workday {
+                total.workday_overtime += day.overtime;
+            } else {
+                total.restday_overtime += day.overtime;
+            }
+            total.shortfall += day.shortfall;
+            total.overtime_days += u32::from(day.overtime > 0.0);
+            total.shortfall_days += u32::from(day.shortfall > 0.0);
+        }
+        date = date.saturating_add(Duration::days(1));
+    }
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+    use crate::calendar::{self, SpecialDay};
+
+    fn insert(db: &Db, date: Date, employee_id: &str, work_minutes: f64) {
+        let attendance = Attendance {
+            employee_id: employee_id.to_string(),
+            work_minutes,
+            ..Default::default()
+        };
+        db.insert(
+            format!("{date}_{employee_id}"),
+            attendance.write_to_vec().unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn workday_restday_and_shortfall() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        // 5月10日周三放假，5月14日周日调休上班
+        calendar::save(
+            &db,
+            &[
+                (
+                    date!(2023 - 05 - 10),
+                    SpecialDay {
+                        workday: false,
+                        name: "假期".to_string(),
+                    },
+                ),
+                (
+                    date!(2023 - 05 - 14),
+                    SpecialDay {
+                        workday: true,
+                        name: "调休".to_string(),
+                    },
+                ),
+            ],
+        )
+        .unwrap();
+        insert(&db, date!(2023 - 05 - 08), "1001", 600.0);
+        insert(&db, date!(2023 - 05 - 09), "1001", 420.0);
+        insert(&db, date!(2023 - 05 - 13), "1001", 300.0);
+        insert(&db, date!(2023 - 05 - 14), "1001", 480.0);
+        insert(&db, date!(2023 - 05 - 10), "1002", 120.0);
+        insert(&db, date!(2023 - 05 - 14), "1002", 540.0);
+        // 日期范围之外
+        insert(&db, date!(2023 - 05 - 15), "1002", 900.0);
+
+        let totals = totals(
+            &db,
+            &OvertimeRule::default(),
+            date!(2023 - 05 - 08),
+            date!(2023 - 05 - 14),
+        )
+        .unwrap();
+        assert_eq!(
+            totals["1001"],
+            OvertimeTotals {
+                workday_overtime: 120.0,
+                restday_overtime: 300.0,
+                shortfall: 60.0,
+                overtime_days: 2,
+                shortfall_days: 1,
+            }
+        );
+        // 放假的日子不算欠时，调休上班的日子按工作日计算
+        assert_eq!(
+            totals["1002"],
+            OvertimeTotals {
+                workday_overtime: 60.0,
+                restday_overtime: 120.0,
+                shortfall: 0.0,
+                overtime_days: 2,
+                shortfall_days: 0,
+            }
+        );
+        assert_eq!(totals["1001"].overtime(), 420.0);
+    }
+
+    #[test]
+    fn restday_standard_hours() {
+        let rule = OvertimeRule {
+            restday_hours: 4.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            rule.day(300.0, false),
+            DayOvertime {
+                overtime: 60.0,
+                shortfall: 0.0
+            }
+        );
+        assert_eq!(
+            rule.day(180.0, false),
+            DayOvertime {
+                overtime: 0.0,
+                shortfall: 60.0
+            }
+        );
+        assert_eq!(rule.day(480.0, true), DayOvertime::default());
+    }
+}
@@ -0,0 +1,264 @@
+//! 原始打卡记录：逐条保存导入的打卡时间，再按班次计算每天的首次、末次打卡和工作时长，
+//! 不再依赖考勤系统导出的工作时长。打卡存在sled的`punches` tree中，
+//! 键为“{日期}_{工号}_{时:分:秒}”，同一次打卡重复导入只保留一条。
+
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+use time::Date;
+
+use crate::date::parse_time_text;
+use crate::store::Attendance;
+
+/// 存放打卡记录的sled tree
+pub(crate) const PUNCH_TREE: &str = "punches";
+
+/// 一次打卡
+#[derive(Debug, Default, Clone, Readable, Writable, PartialEq)]
+pub struct Punch {
+    pub employee_id: String,
+    /// 打卡时间，当天的第几秒
+    pub seconds: u32,
+    /// 打卡设备或地点
+    pub device: String,
+}
+
+impl Punch {
+    pub fn key(&self, date: Date) -> String {
+        format!(
+            "{date}_{}_{:02}:{:02}:{:02}",
+            self.employee_id,
+            self.seconds / 3600,
+            self.seconds / 60 % 60,
+            self.seconds % 60
+        )
+    }
+}
+
+/// 某员工某天保存的全部打卡
+pub fn day_punches(db: &Db, date: Date, employee_id: &str) -> Result<Vec<Punch>> {
+    let punches = db.open_tree(PUNCH_TREE)?;
+    let mut day = Vec::new();
+    for kv in punches.scan_prefix(format!("{date}_{employee_id}_")) {
+        let (_, value) = kv?;
+        // 工号本身带“_”时前缀可能匹配到别的员工
+        match Punch::read_from_buffer(&value) {
+            Ok(punch) if punch.employee_id == employee_id => day.push(punch),
+            _ => {}
+        }
+    }
+    Ok(day)
+}
+
+/// 计算工时用的班次，时间均为当天的第几分钟，不支持跨天的班次
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct Shift {
+    /// 上班时间，晚于它打卡为迟到，早于它打卡的时间不计入工时
+    pub start: u32,
+    /// 下班时间，早于它打卡为早退，晚于它的时间计入工时
+    pub end: u32,
+    /// 午休开始，午休时间不计入工时，与午休结束相同时不扣除
+    pub lunch_start: u32,
+    pub lunch_end: u32,
+}
+
+impl Default for Shift {
+    fn default() -> Self {
+        Shift {
+            start: 9 * 60,
+            end: 18 * 60,
+            lunch_start: 12 * 60,
+            lunch_end: 13 * 60,
+        }
+    }
+}
+
+impl Shift {
+    pub fn check(&self) -> Result<()> {
+        if self.start >= self.end {
+            return Err(anyhow!("班次的下班时间须晚于上班时间"));
+        }
+        if self.lunch_start > self.lunch_end
+            || self.lunch_start < self.start
+            || self.lunch_end > self.end
+        {
+            return Err(anyhow!("午休时间须在上下班时间之间，且开始不晚于结束"));
+        }
+        Ok(())
+    }
+
+    /// 按当天的全部打卡（秒）写入考勤的首次、末次打卡和工作时长，工作时长此后以打卡为准
+    ///
+    /// 上下班打卡结果以每日统计表为准，其中有请假、外勤等打卡算不出的结果，
+    /// 只在还没有结果或结果本来就是按打卡算的时候，才按班次算出缺卡、迟到、早退。
+    /// 只有一次打卡时，早于班次中点算上班打卡，否则算下班打卡，工作时长为0
+    pub fn apply(&self, seconds: &[u32], attendance: &mut Attendance) {
+        let (Some(&first), Some(&last)) = (seconds.iter().min(), seconds.iter().max()) else {
+            return;
+        };
+        let (first_in, last_out) = if first != last {
+            (Some(first), Some(last))
+        } else if first / 60 < (self.start + self.end) / 2 {
+            (Some(first), None)
+        } else {
+            (None, Some(last))
+        };
+
+        attendance.first_in = first_in.map(format_seconds).unwrap_or_default();
+        attendance.last_out = last_out.map(format_seconds).unwrap_or_default();
+        attendance.work_minutes = match (first_in, last_out) {
+            (Some(first), Some(last)) => {
+                let from = first.max(self.start * 60);
+                let lunch = overlap((from, last), (self.lunch_start * 60, self.lunch_end * 60));
+                (last.saturating_sub(from).saturating_sub(lunch) / 60) as f64
+            }
+            _ => 0.0,
+        };
+        attendance.minutes_from_punches = true;
+
+        // 每日统计表导入的结果不覆盖
+        let no_results = attendance.enter_info.is_empty() && attendance.leave_info.is_empty();
+        if !(attendance.results_from_punches || no_results) {
+            return;
+        }
+        attendance.enter_info = match first_in {
+            None => "缺卡".to_string(),
+            Some(first) if first / 60 > self.start => {
+                format!("迟到{}分钟", first / 60 - self.start)
+            }
+            Some(_) => "正常".to_string(),
+        };
+        attendance.leave_info = match last_out {
+            None => "缺卡".to_string(),
+            Some(last) if last / 60 < self.end => format!("早退{}分钟", self.end - last / 60),
+            Some(_) => "正常".to_string(),
+        };
+        attendance.results_from_punches = true;
+    }
+}
+
+/// 两个时间段重叠的长度
+fn overlap((start, end): (u32, u32), (other_start, other_end): (u32, u32)) -> u32 {
+    end.min(other_end).saturating_sub(start.max(other_start))
+}
+
+/// 当天第几秒显示为“时:分”
+fn format_seconds(seconds: u32) -> String {
+    format_minutes(seconds / 60)
+}
+
+/// 当天第几分钟显示为“时:分”
+pub fn format_minutes(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// 解析“9:00”之类的时间为当天第几分钟
+pub fn parse_minutes(text: &str) -> Option<u32> {
+    let time = parse_time_text(text)?;
+    Some(time.hour() as u32 * 60 + time.minute() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// “时:分”换算为当天第几秒
+    fn at(text: &str) -> u32 {
+        parse_minutes(text).unwrap() * 60
+    }
+
+    fn apply(punches: &[&str], attendance: &mut Attendance) {
+        let seconds = punches.iter().map(|text| at(text)).collect::<Vec<_>>();
+        Shift::default().apply(&seconds, attendance);
+    }
+
+    fn punched(punches: &[&str]) -> Attendance {
+        let mut attendance = Attendance::default();
+        apply(punches, &mut attendance);
+        attendance
+    }
+
+    #[test]
+    fn single_punch_counts_by_shift_midpoint() {
+        // 班次中点为13:30
+        let attendance = punched(&["09:10"]);
+        assert_eq!(
+            (attendance.first_in.as_str(), attendance.last_out.as_str()),
+            ("09:10", "")
+        );
+        assert_eq!(attendance.enter_info, "迟到10分钟");
+        assert_eq!(attendance.leave_info, "缺卡");
+        assert_eq!(attendance.work_minutes, 0.0);
+
+        let attendance = punched(&["17:45"]);
+        assert_eq!(
+            (attendance.first_in.as_str(), attendance.last_out.as_str()),
+            ("", "17:45")
+        );
+        assert_eq!(attendance.enter_info, "缺卡");
+        assert_eq!(attendance.leave_info, "早退15分钟");
+        assert_eq!(attendance.work_minutes, 0.0);
+    }
+
+    #[test]
+    fn time_before_shift_start_is_not_counted() {
+        let attendance = punched(&["18:30", "08:05", "12:10"]);
+        assert_eq!(
+            (attendance.first_in.as_str(), attendance.last_out.as_str()),
+            ("08:05", "18:30")
+        );
+        assert_eq!(
+            (
+                attendance.enter_info.as_str(),
+                attendance.leave_info.as_str()
+            ),
+            ("正常", "正常")
+        );
+        // 9:00到18:30，扣除午休1小时
+        assert_eq!(attendance.work_minutes, 510.0);
+    }
+
+    #[test]
+    fn lunch_break_is_deducted_only_where_it_overlaps() {
+        // 13:00之后才上班，不扣午休
+        assert_eq!(punched(&["13:00", "18:00"]).work_minutes, 300.0);
+        // 12:30上班，扣除12:30到13:00
+        assert_eq!(punched(&["12:30", "18:00"]).work_minutes, 300.0);
+        // 12:30下班，扣除12:00到12:30
+        assert_eq!(punched(&["09:00", "12:30"]).work_minutes, 180.0);
+        // 整个在午休内
+        assert_eq!(punched(&["12:10", "12:50"]).work_minutes, 0.0);
+        assert_eq!(punched(&["09:00", "18:00"]).work_minutes, 480.0);
+    }
+
+    #[test]
+    fn imported_results_are_kept() {
+        let mut attendance = Attendance {
+            enter_info: "请假".to_string(),
+            leave_info: "正常".to_string(),
+            work_minutes: 240.0,
+            ..Default::default()
+        };
+        apply(&["13:00", "18:05"], &mut attendance);
+        assert_eq!(
+            (
+                attendance.enter_info.as_str(),
+                attendance.leave_info.as_str()
+            ),
+            ("请假", "正常")
+        );
+        assert_eq!(attendance.work_minutes, 305.0);
+        assert!(attendance.minutes_from_punches);
+
+        // 按打卡算出的结果再导入打卡时重新计算
+        let mut attendance = punched(&["09:20"]);
+        apply(&["09:20", "18:00"], &mut attendance);
+        assert_eq!(
+            (
+                attendance.enter_info.as_str(),
+                attendance.leave_info.as_str()
+            ),
+            ("迟到20分钟", "正常")
+        );
+    }
+}
@@ -0,0 +1,1383 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+use time::{macros::format_description, Date, OffsetDateTime, UtcOffset};
+use umya_spreadsheet::helper::coordinate::string_from_column_index;
+use umya_spreadsheet::{
+    HorizontalAlignmentValues, NumberingFormat, Spreadsheet, Style, VerticalAlignmentValues,
+    Worksheet,
+};
+
+use crate::calendar;
+use crate::highlight::{self, HighlightRule};
+use crate::input::{SheetConfig, UserInput, USER_INPUT_KEY};
+use crate::keyword::{self, KeywordRule};
+use crate::overtime::{self, OvertimeRule, OvertimeTotals};
+use crate::store::Attendance;
+
+/// 写核对结果的sheet，放在最后，重新生成时替换
+pub const CHECK_SHEET: &str = "核对";
+/// 写加班统计的sheet，开启加班统计时放在核对sheet之前，重新生成时替换
+pub const OVERTIME_SHEET: &str = "加班统计";
+/// 生成结果中最多直接列出多少条核对结果，其余见核对sheet
+const CHECK_DISPLAY_LIMIT: usize = 10;
+/// 记录生成的报表每个sheet写到哪一列的sled tree，键为“{报表路径}\n{sheet名称}”，
+/// 值为写到的最后一列的下一列（大端u32）。在上次生成的报表上重新生成时，按它清掉上次多写的列
+const WRITTEN_COLS_TREE: &str = "report_written_cols";
+
+/// 一次生成的结果，Display输出可直接展示给用户
+#[derive(Debug, PartialEq)]
+pub struct ReportSummary {
+    /// 生成的报表
+    pub output: PathBuf,
+    /// 覆盖模板时原模板的备份
+    pub backup: Option<PathBuf>,
+    /// 模板中有、当天却没有考勤记录的员工
+    pub missing_data: Vec<Missing>,
+    /// 当天有考勤记录、却不在任何模板sheet中的员工
+    pub missing_template: Vec<Missing>,
+}
+
+/// 模板和考勤数据对不上的一个员工
+#[derive(Debug, PartialEq)]
+pub struct Missing {
+    /// 模板sheet名称，不在模板中的员工为None
+    pub sheet: Option<String>,
+    pub date: Date,
+    pub employee_id: String,
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.sheet {
+            Some(sheet) => write!(f, "{} {} 工号{}", self.date, sheet, self.employee_id),
+            None => write!(f, "{} 工号{}", self.date, self.employee_id),
+        }
+    }
+}
+
+impl fmt::Display for ReportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "生成完成：{}", self.output.display())?;
+        if let Some(backup) = &self.backup {
+            write!(f, "\n原模板已备份为：{}", backup.display())?;
+        }
+        for (title, missing) in [
+            ("模板中有、当天没有考勤数据", &self.missing_data),
+            ("有考勤数据、模板中没有", &self.missing_template),
+        ] {
+            if missing.is_empty() {
+                continue;
+            }
+            write!(f, "\n{title}：{}处", missing.len())?;
+            for item in missing.iter().take(CHECK_DISPLAY_LIMIT) {
+                write!(f, "\n  {item}")?;
+            }
+            if missing.len() > CHECK_DISPLAY_LIMIT {
+                write!(f, "\n  ……其余见“{CHECK_SHEET}”sheet")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 默认的报表文件名：考勤_{开始日期}_{结束日期}.xlsx
+pub fn default_output_name(user_input: &UserInput) -> Result<String> {
+    Ok(format!(
+        "考勤_{}_{}.xlsx",
+        Date::from_julian_day(user_input.start_date)?,
+        Date::from_julian_day(user_input.end_date)?
+    ))
+}
+
+/// 按模板生成报表并另存到`output`，模板文件不变；`output`就是模板本身时先备份模板
+pub fn generate_report(
+    template: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    user_input: &UserInput,
+    db: &Db,
+) -> Result<ReportSummary> {
+    let (template, output) = (template.as_ref(), output.as_ref());
+    db.insert(USER_INPUT_KEY, user_input.write_to_vec()?)?;
+    let mut book = read_template(template)?;
+    // 只为上班的日子生成列，跳过周末和节假日，包含调休上班日
+    let workdays = calendar::workdays(
+        db,
+        Date::from_julian_day(user_input.start_date)?,
+        Date::from_julian_day(user_input.end_date)?,
+    )?;
+    let rules = keyword::load(db)?;
+    let highlights = highlight::load(db)?;
+    let mut missing_data = Vec::new();
+    // 所有模板sheet中的工号
+    let mut template_ids = HashSet::new();
+
+    let written_cols = db.open_tree(WRITTEN_COLS_TREE)?;
+    // 每个sheet写到的列，保存报表后记下
+    let mut written = Vec::new();
+
+    let sheet_indexes = resolve_sheets(&book, &user_input.template_cfg)?;
+    // 加班按日期范围内的每一天统计，包括周末和节假日
+    let overtime_totals = if user_input.overtime.enabled {
+        Some(overtime::totals(
+            db,
+            &user_input.overtime,
+            Date::from_julian_day(user_input.start_date)?,
+            Date::from_julian_day(user_input.end_date)?,
+        )?)
+    } else {
+        None
+    };
+
+    for (sheet_index, template_cfg) in sheet_indexes.into_iter().zip(&user_input.template_cfg) {
+        let worksheet = book.get_sheet_mut(&sheet_index).map_err(|e| anyhow!(e))?;
+        let (max_col, max_row) = worksheet.get_highest_column_and_row();
+        let sheet_name = worksheet.get_name().to_string();
+
+        // 本次要写到的列，以及模板是上次生成的报表时上次写到的列
+        let mut end_col =
+            template_cfg.start_col + (template_cfg.day_columns.len() * workdays.len()) as u32;
+        if user_input.summary_columns {
+            end_col += Summary::column_count(&rules, overtime_totals.is_some());
+        }
+        let written_col = written_cols
+            .get(written_key(template, &sheet_name))?
+            .and_then(|value| value.as_ref().try_into().ok())
+            .map_or(0, u32::from_be_bytes);
+        if user_input.clear_block {
+            // 清掉上次生成留下的内容，包括上次日期更多时多写的列，模板中数据区域右边的列和工号列不动
+            for r in template_cfg.title_row..max_row + 1 {
+                let reference = worksheet
+                    .get_style((template_cfg.employee_id_col, r))
+                    .clone();
+                for c in template_cfg.start_col..end_col.max(written_col).min(max_col + 1) {
+                    if c == template_cfg.employee_id_col {
+                        continue;
+                    }
+                    let cell = worksheet.get_cell_mut((c, r));
+                    cell.set_blank();
+                    user_input
+                        .style_preset
+                        .clear(cell.get_style_mut(), &reference, &highlights);
+                }
+            }
+            written.push((sheet_name.clone(), end_col));
+        } else {
+            written.push((sheet_name.clone(), end_col.max(written_col)));
+        }
+
+        let columns = &template_cfg.day_columns;
+        let work_time = &template_cfg.work_time;
+        let mut date_col = template_cfg.start_col;
+        // 行号 -> 该员工在日期范围内的合计
+        let mut totals = HashMap::new();
+        for &loop_date in &workdays {
+            let headers = columns
+                .iter()
+                .map(|column| render_header(&column.header, loop_date))
+                .collect::<Result<Vec<_>>>()?;
+            let every_atd =
+                db.scan_prefix(format!("{loop_date}_"))
+                    .fold(HashMap::new(), |mut map, kv| {
+                        if let Ok((_, value)) = kv {
+                            if let Ok(attendance) = Attendance::read_from_buffer(&value) {
+                                map.insert(attendance.employee_id.clone(), attendance);
+                            }
+                        }
+                        map
+                    });
+
+            for r in template_cfg.title_row..max_row + 1 {
+                // 沿用模板样式时参考同一行工号单元格的样式
+                let reference = worksheet
+                    .get_style((template_cfg.employee_id_col, r))
+                    .clone();
+                if r == template_cfg.title_row {
+                    // 写表头
+                    for (i, (column, header)) in columns.iter().zip(&headers).enumerate() {
+                        let col = date_col + i as u32;
+                        let style = worksheet
+                            .get_cell_mut((col, r))
+                            .set_value_string(header.as_str())
+                            .get_style_mut();
+                        user_input.style_preset.apply(style, &reference);
+                        if column.width > 0.0 {
+                            worksheet
+                                .get_column_dimension_mut(&string_from_column_index(&col))
+                                .set_width(column.width);
+                        }
+                    }
+
+                    continue;
+                }
+
+                let employee_id = worksheet.get_formatted_value((template_cfg.employee_id_col, r));
+                if employee_id.is_empty() {
+                    continue;
+                }
+                template_ids.insert(employee_id.clone());
+                if let Some(attendance) = every_atd.get(&employee_id) {
+                    let (status, matched) = keyword::summarize(&rules, attendance);
+                    for (i, column) in columns.iter().enumerate() {
+                        let cell = worksheet.get_cell_mut((date_col + i as u32, r));
+                        let text = match column.field {
+                            DayField::Hours | DayField::Overtime | DayField::Shortfall => {
+                                // 报表中只有上班的日子，按工作日的标准工时计算
+                                let day = user_input.overtime.day(attendance.work_minutes, true);
+                                let minutes = match column.field {
+                                    DayField::Overtime => day.overtime,
+                                    DayField::Shortfall => day.shortfall,
+                                    _ => attendance.work_minutes,
+                                };
+                                let style = cell
+                                    .set_value_number(work_time.convert(minutes))
+                                    .get_style_mut();
+                                user_input.style_preset.apply(style, &reference);
+                                style
+                                    .get_numbering_format_mut()
+                                    .set_format_code(work_time.number_format());
+                                // 高亮规则的阈值是小时数，与工时单位无关
+                                let hours = attendance.work_minutes / 60.0;
+                                let rule = match column.field {
+                                    DayField::Hours => highlight::for_hours(&highlights, hours),
+                                    _ => None,
+                                };
+                                apply_highlight(style, &highlights, rule);
+                                continue;
+                            }
+                            DayField::Status => status.as_str(),
+                            DayField::Reason => attendance.abnormal_reason.as_str(),
+                            DayField::EnterInfo => attendance.enter_info.as_str(),
+                            DayField::LeaveInfo => attendance.leave_info.as_str(),
+                            DayField::FirstIn => attendance.first_in.as_str(),
+                            DayField::LastOut => attendance.last_out.as_str(),
+                        };
+                        let style = cell.set_value_string(text).get_style_mut();
+                        user_input.style_preset.apply(style, &reference);
+                        let rule = highlight::for_status(
+                            &highlights,
+                            &[
+                                &status,
+                                &attendance.enter_info,
+                                &attendance.leave_info,
+                                &attendance.abnormal_reason,
+                            ],
+                        );
+                        apply_highlight(style, &highlights, rule);
+                    }
+
+                    let total = totals.entry(r).or_insert_with(|| Totals {
+                        category_days: vec![0; rules.len()],
+                        ..Default::default()
+                    });
+                    total.work_time += work_time.convert(attendance.work_minutes);
+                    total.days += 1;
+                    if !status.is_empty() {
+                        total.abnormal_days += 1;
+                    }
+                    for index in matched {
+                        total.category_days[index] += 1;
+                    }
+                } else {
+                    missing_data.push(Missing {
+                        sheet: Some(sheet_name.clone()),
+                        date: loop_date,
+                        employee_id,
+                    });
+                    if !user_input.no_data_text.is_empty() {
+                        // 写在考勤列，没有考勤列时写在第一列
+                        let index = columns
+                            .iter()
+                            .position(|column| column.field == DayField::Status)
+                            .unwrap_or_default();
+                        let style = worksheet
+                            .get_cell_mut((date_col + index as u32, r))
+                            .set_value_string(user_input.no_data_text.as_str())
+                            .get_style_mut();
+                        user_input.style_preset.apply(style, &reference);
+                        apply_highlight(style, &highlights, None);
+                    }
+                }
+            }
+
+            date_col += columns.len() as u32;
+        }
+
+        if user_input.summary_columns {
+            let summary = Summary {
+                template_cfg,
+                max_row,
+                start_col: date_col,
+                style_preset: user_input.style_preset,
+            };
+            summary.write(worksheet, &rules, &totals, overtime_totals.as_ref());
+        }
+    }
+
+    let mut missing_template = Vec::new();
+    for &date in &workdays {
+        for kv in db.scan_prefix(format!("{date}_")) {
+            let (_, value) = kv?;
+            let Ok(attendance) = Attendance::read_from_buffer(&value) else {
+                continue;
+            };
+            if !template_ids.contains(&attendance.employee_id) {
+                missing_template.push(Missing {
+                    sheet: None,
+                    date,
+                    employee_id: attendance.employee_id,
+                });
+            }
+        }
+    }
+    if let Some(overtime_totals) = &overtime_totals {
+        write_overtime_sheet(&mut book, &user_input.overtime, overtime_totals)?;
+    }
+    write_check_sheet(&mut book, &missing_data, &missing_template)?;
+
+    let backup = if is_same_file(template, output) {
+        Some(backup(template)?)
+    } else {
+        None
+    };
+    umya_spreadsheet::writer::xlsx::write(&book, output)
+        .map_err(|e| anyhow!("保存报表到{}失败：{e}", output.display()))?;
+    for (sheet_name, end_col) in written {
+        written_cols.insert(written_key(output, &sheet_name), &end_col.to_be_bytes())?;
+    }
+    Ok(ReportSummary {
+        output: output.to_path_buf(),
+        backup,
+        missing_data,
+        missing_template,
+    })
+}
+
+/// 报表某个sheet在[`WRITTEN_COLS_TREE`]中的键，路径取绝对路径
+fn written_key(path: &Path, sheet_name: &str) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    format!("{}\n{sheet_name}", path.display())
+}
+
+/// 一个员工在日期范围内的合计
+#[derive(Default)]
+struct Totals {
+    /// 每天换算、取整后的工时之和
+    work_time: f64,
+    /// 有考勤记录的天数
+    days: u32,
+    /// 考勤文字不为空的天数
+    abnormal_days: u32,
+    /// 每条关键词规则出现的天数
+    category_days: Vec<u32>,
+}
+
+/// 汇总列的位置和样式
+struct Summary<'a> {
+    template_cfg: &'a SheetConfig,
+    max_row: u32,
+    /// 第一个汇总列，即最后一天之后的一列
+    start_col: u32,
+    style_preset: StylePreset,
+}
+
+impl Summary<'_> {
+    /// 汇总列的列数
+    fn column_count(rules: &[KeywordRule], overtime: bool) -> u32 {
+        3 + rules.len() as u32 + if overtime { 3 } else { 0 }
+    }
+
+    /// 在最后一天之后写合计工时、日均工时、异常天数和每类异常的天数，
+    /// 开启加班统计时再加上工作日加班、休息日加班和欠时的合计
+    fn write(
+        &self,
+        worksheet: &mut Worksheet,
+        rules: &[KeywordRule],
+        totals: &HashMap<u32, Totals>,
+        overtime_totals: Option<&BTreeMap<String, OvertimeTotals>>,
+    ) {
+        let SheetConfig {
+            employee_id_col,
+            title_row,
+            ref work_time,
+            ..
+        } = *self.template_cfg;
+        // 工时单位不是小时时在表头注明
+        let unit = match work_time.unit {
+            WorkTimeUnit::Hours => String::new(),
+            unit => format!("（{}）", unit.name()),
+        };
+        let headers = [
+            format!("合计工时{unit}"),
+            format!("日均工时{unit}"),
+            "异常天数".to_string(),
+        ]
+        .into_iter()
+        .chain(rules.iter().map(|rule| format!("{}天数", rule.label())))
+        .chain(
+            overtime_totals
+                .map(|_| {
+                    [
+                        format!("工作日加班{unit}"),
+                        format!("休息日加班{unit}"),
+                        format!("欠时{unit}"),
+                    ]
+                })
+                .into_iter()
+                .flatten(),
+        );
+        let reference = worksheet.get_style((employee_id_col, title_row)).clone();
+        for (i, header) in headers.enumerate() {
+            let style = worksheet
+                .get_cell_mut((self.start_col + i as u32, title_row))
+                .set_value_string(header)
+                .get_style_mut();
+            self.style_preset.apply(style, &reference);
+        }
+
+        let empty = Totals {
+            category_days: vec![0; rules.len()],
+            ..Default::default()
+        };
+        for r in title_row + 1..self.max_row + 1 {
+            let employee_id = worksheet.get_formatted_value((employee_id_col, r));
+            if employee_id.is_empty() {
+                continue;
+            }
+            let reference = worksheet.get_style((employee_id_col, r)).clone();
+            let total = totals.get(&r).unwrap_or(&empty);
+            let sum = work_time.round(total.work_time);
+            let average = (total.days > 0).then(|| work_time.round(sum / total.days as f64));
+            for (col, value) in [(self.start_col, Some(sum)), (self.start_col + 1, average)] {
+                let cell = worksheet.get_cell_mut((col, r));
+                if let Some(value) = value {
+                    cell.set_value_number(value);
+                }
+                let style = cell.get_style_mut();
+                self.style_preset.apply(style, &reference);
+                style
+                    .get_numbering_format_mut()
+                    .set_format_code(work_time.number_format());
+            }
+            let counts = [total.abnormal_days]
+                .into_iter()
+                .chain(total.category_days.iter().copied());
+            for (i, count) in counts.enumerate() {
+                let style = worksheet
+                    .get_cell_mut((self.start_col + 2 + i as u32, r))
+                    .set_value_number(count)
+                    .get_style_mut();
+                self.style_preset.apply(style, &reference);
+                style
+                    .get_numbering_format_mut()
+                    .set_format_code(NumberingFormat::FORMAT_GENERAL);
+            }
+
+            let Some(overtime_totals) = overtime_totals else {
+                continue;
+            };
+            let overtime = overtime_totals
+                .get(&employee_id)
+                .cloned()
+                .unwrap_or_default();
+            let col = self.start_col + 3 + rules.len() as u32;
+            for (i, minutes) in [
+                overtime.workday_overtime,
+                overtime.restday_overtime,
+                overtime.shortfall,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let style = worksheet
+                    .get_cell_mut((col + i as u32, r))
+                    .set_value_number(work_time.convert(minutes))
+                    .get_style_mut();
+                self.style_preset.apply(style, &reference);
+                style
+                    .get_numbering_format_mut()
+                    .set_format_code(work_time.number_format());
+            }
+        }
+    }
+}
+
+/// 找出每个模板配置对应的sheet序号，在写入前检查sheet名称和数量
+fn resolve_sheets(book: &Spreadsheet, template_cfg: &[SheetConfig]) -> Result<Vec<usize>> {
+    let names = book
+        .get_sheet_collection()
+        .iter()
+        .map(|worksheet| worksheet.get_name())
+        .collect::<Vec<_>>();
+    let mut indexes = Vec::with_capacity(template_cfg.len());
+    for (i, cfg) in template_cfg.iter().enumerate() {
+        let index = if cfg.sheet_name.is_empty() {
+            if i >= names.len() {
+                return Err(anyhow!(
+                    "模板只有{}个sheet，第{}个模板配置没有对应的sheet，请检查模板配置",
+                    names.len(),
+                    i + 1
+                ));
+            }
+            i
+        } else {
+            names
+                .iter()
+                .position(|name| *name == cfg.sheet_name)
+                .ok_or(anyhow!(
+                    "模板中找不到名为“{}”的sheet，现有的sheet：{}",
+                    cfg.sheet_name,
+                    names.join("、")
+                ))?
+        };
+        cfg.work_time
+            .check()
+            .map_err(|e| anyhow!("第{}个模板配置，{e}", i + 1))?;
+        if cfg.day_columns.is_empty() {
+            return Err(anyhow!(
+                "第{}个模板配置没有设置每日列，请检查模板配置",
+                i + 1
+            ));
+        }
+        if [CHECK_SHEET, OVERTIME_SHEET].contains(&names[index]) {
+            return Err(anyhow!(
+                "第{}个模板配置对应的是生成的“{}”sheet，请检查模板配置",
+                i + 1,
+                names[index]
+            ));
+        }
+        if let Some(other) = indexes.iter().position(|&other| other == index) {
+            return Err(anyhow!(
+                "第{}个和第{}个模板配置都对应sheet“{}”，请检查模板配置",
+                other + 1,
+                i + 1,
+                names[index]
+            ));
+        }
+        indexes.push(index);
+    }
+    Ok(indexes)
+}
+
+/// 模板中所有sheet的名称
+pub fn sheet_names(path: impl AsRef<Path>) -> Result<Vec<String>> {
+    let book = read_template(path.as_ref())?;
+    Ok(book
+        .get_sheet_collection()
+        .iter()
+        .map(|worksheet| worksheet.get_name().to_string())
+        .collect())
+}
+
+/// 读取模板，模板只支持xlsx，xls等其他格式直接给出提示，不交给xlsx解析器报错
+fn read_template(path: &Path) -> Result<Spreadsheet> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !matches!(extension.as_str(), "xlsx" | "xlsm") {
+        return Err(anyhow!(
+            "模板须为xlsx文件：{}，xls模板请先在Excel中另存为xlsx",
+            path.display()
+        ));
+    }
+    umya_spreadsheet::reader::xlsx::read(path)
+        .map_err(|e| anyhow!("读取模板{}失败：{e}", path.display()))
+}
+
+/// 加一个加班统计sheet，按工号列出每个员工的加班和欠时合计，单位为小时，已有的先删掉
+fn write_overtime_sheet(
+    book: &mut Spreadsheet,
+    rule: &OvertimeRule,
+    totals: &BTreeMap<String, OvertimeTotals>,
+) -> Result<()> {
+    book.remove_sheet_by_name(OVERTIME_SHEET).ok();
+    let worksheet = book.new_sheet(OVERTIME_SHEET).map_err(|e| anyhow!(e))?;
+    let headers = [
+        "工号".to_string(),
+        format!("工作日加班（小时，标准{}小时）", rule.workday_hours),
+        format!("休息日加班（小时，标准{}小时）", rule.restday_hours),
+        "加班合计（小时）".to_string(),
+        "欠时（小时）".to_string(),
+        "加班天数".to_string(),
+        "欠时天数".to_string(),
+    ];
+    for (c, header) in headers.into_iter().enumerate() {
+        let c = c as u32 + 1;
+        let style = worksheet
+            .get_cell_mut((c, 1))
+            .set_value_string(header)
+            .get_style_mut();
+        center_wrap(style);
+        worksheet
+            .get_column_dimension_mut(&string_from_column_index(&c))
+            .set_width(if c == 1 { 15.0 } else { 12.0 });
+    }
+
+    for (r, (employee_id, total)) in totals.iter().enumerate() {
+        let r = r as u32 + 2;
+        worksheet
+            .get_cell_mut((1, r))
+            .set_value_string(employee_id.as_str());
+        let hours = [
+            total.workday_overtime,
+            total.restday_overtime,
+            total.overtime(),
+            total.shortfall,
+        ];
+        for (c, minutes) in hours.into_iter().enumerate() {
+            worksheet
+                .get_cell_mut((c as u32 + 2, r))
+                .set_value_number(minutes / 60.0)
+                .get_style_mut()
+                .get_numbering_format_mut()
+                .set_format_code(NumberingFormat::FORMAT_NUMBER_00);
+        }
+        worksheet
+            .get_cell_mut((6, r))
+            .set_value_number(total.overtime_days);
+        worksheet
+            .get_cell_mut((7, r))
+            .set_value_number(total.shortfall_days);
+    }
+    Ok(())
+}
+
+/// 在最后加一个核对sheet列出对不上的员工，已有的先删掉
+fn write_check_sheet(
+    book: &mut Spreadsheet,
+    missing_data: &[Missing],
+    missing_template: &[Missing],
+) -> Result<()> {
+    book.remove_sheet_by_name(CHECK_SHEET).ok();
+    let worksheet = book.new_sheet(CHECK_SHEET).map_err(|e| anyhow!(e))?;
+    for (c, (header, width)) in [
+        ("类型", 28.0),
+        ("Sheet", 15.0),
+        ("日期", 12.0),
+        ("工号", 15.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let c = c as u32 + 1;
+        worksheet.get_cell_mut((c, 1)).set_value_string(header);
+        worksheet
+            .get_column_dimension_mut(&string_from_column_index(&c))
+            .set_width(width);
+    }
+
+    let rows = missing_data
+        .iter()
+        .map(|missing| ("模板中有、当天没有考勤数据", missing))
+        .chain(
+            missing_template
+                .iter()
+                .map(|missing| ("有考勤数据、模板中没有", missing)),
+        );
+    for (r, (kind, missing)) in rows.enumerate() {
+        let r = r as u32 + 2;
+        worksheet.get_cell_mut((1, r)).set_value_string(kind);
+        worksheet
+            .get_cell_mut((2, r))
+            .set_value_string(missing.sheet.as_deref().unwrap_or("全部"));
+        worksheet
+            .get_cell_mut((3, r))
+            .set_value_string(missing.date.to_string());
+        worksheet
+            .get_cell_mut((4, r))
+            .set_value_string(missing.employee_id.as_str());
+    }
+    Ok(())
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 在模板旁复制一份“{文件名}_备份_{年月日_时分秒}.xlsx”
+fn backup(template: &Path) -> Result<PathBuf> {
+    let now = OffsetDateTime::now_utc()
+        .to_offset(UtcOffset::from_hms(8, 0, 0)?)
+        .format(format_description!(
+            "[year][month][day]_[hour][minute][second]"
+        ))?;
+    let stem = template
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = template
+        .extension()
+        .map(|ext| ext.to_string_lossy())
+        .unwrap_or_default();
+    let backup = template.with_file_name(format!("{stem}_备份_{now}.{extension}"));
+    fs::copy(template, &backup).map_err(|e| anyhow!("备份模板到{}失败：{e}", backup.display()))?;
+    Ok(backup)
+}
+
+/// 给单元格加上匹配的高亮规则，没有匹配时为None
+///
+/// 先去掉上次生成留下的高亮，即与某条规则的颜色相同的填充色和字体颜色，模板本身的颜色不动，
+/// 这样不清空数据区域、在上次的报表上重新生成时，已恢复正常的单元格不会留着旧的高亮
+fn apply_highlight(style: &mut Style, rules: &[HighlightRule], rule: Option<&HighlightRule>) {
+    let is_rule_color = |argb: &str, color: fn(&HighlightRule) -> &str| {
+        rules.iter().any(|rule| {
+            !color(rule).is_empty() && argb.eq_ignore_ascii_case(&format!("FF{}", color(rule)))
+        })
+    };
+    if style
+        .get_background_color()
+        .is_some_and(|color| is_rule_color(color.get_argb(), |rule| &rule.fill_color))
+    {
+        style.remove_fill();
+    }
+    if style
+        .get_font()
+        .is_some_and(|font| is_rule_color(font.get_color().get_argb(), |rule| &rule.font_color))
+    {
+        style.get_font_mut().get_color_mut().set_argb("FF000000");
+    }
+
+    let Some(rule) = rule else {
+        return;
+    };
+    if !rule.fill_color.is_empty() {
+        style.set_background_color(format!("FF{}", rule.fill_color));
+    }
+    if !rule.font_color.is_empty() {
+        style
+            .get_font_mut()
+            .get_color_mut()
+            .set_argb(format!("FF{}", rule.font_color));
+    }
+}
+
+/// 每日列中写的内容
+#[derive(Debug, Clone, Copy, Readable, Writable, PartialEq)]
+pub enum DayField {
+    /// 工作时长，单位和取整见[`WorkTime`]
+    Hours,
+    /// 按关键词规则得到的考勤文字
+    Status,
+    /// 原始记录表中的异常打卡原因
+    Reason,
+    /// 每日统计表中的上班打卡结果
+    EnterInfo,
+    /// 每日统计表中的下班打卡结果
+    LeaveInfo,
+    /// 比工作日标准工时多出的时间，单位同工时
+    Overtime,
+    /// 比工作日标准工时不足的时间，单位同工时
+    Shortfall,
+    /// 打卡记录中的首次打卡时间
+    FirstIn,
+    /// 打卡记录中的末次打卡时间
+    LastOut,
+}
+
+impl DayField {
+    pub const ALL: [DayField; 9] = [
+        DayField::Hours,
+        DayField::Status,
+        DayField::Reason,
+        DayField::EnterInfo,
+        DayField::LeaveInfo,
+        DayField::Overtime,
+        DayField::Shortfall,
+        DayField::FirstIn,
+        DayField::LastOut,
+    ];
+
+    /// 内容的名称，也用于命令行参数和界面
+    pub const NAMES: [&'static str; 9] = [
+        "工时",
+        "考勤",
+        "异常原因",
+        "上班打卡结果",
+        "下班打卡结果",
+        "加班",
+        "欠时",
+        "首次打卡",
+        "末次打卡",
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let index = Self::NAMES.iter().position(|n| *n == name.trim())?;
+        Some(Self::ALL[index])
+    }
+
+    /// 没有填写表头时使用的表头
+    pub fn default_header(self) -> &'static str {
+        match self {
+            DayField::Hours => "{date}个人投入度",
+            DayField::Status => "{date}考勤\n（正常/不正常（缺卡、补卡、虚拟打卡、非主责项目或城市打卡），不正常说明原因）",
+            DayField::Reason => "{date}异常原因",
+            DayField::EnterInfo => "{date}上班打卡结果",
+            DayField::LeaveInfo => "{date}下班打卡结果",
+            DayField::Overtime => "{date}加班",
+            DayField::Shortfall => "{date}欠时",
+            DayField::FirstIn => "{date}首次打卡",
+            DayField::LastOut => "{date}末次打卡",
+        }
+    }
+}
+
+/// 每天的一列，每天按顺序写一组这样的列
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct DayColumn {
+    pub field: DayField,
+    /// 表头模板，占位符见[`render_header`]
+    pub header: String,
+    /// 列宽，为0时不改
+    pub width: f64,
+}
+
+impl DayColumn {
+    pub fn new(field: DayField, width: f64) -> Self {
+        DayColumn {
+            field,
+            header: field.default_header().to_string(),
+            width,
+        }
+    }
+}
+
+/// 默认的每日列：工时一列、考勤一列，与早期版本一致
+pub fn default_day_columns() -> Vec<DayColumn> {
+    vec![
+        DayColumn::new(DayField::Hours, 0.0),
+        DayColumn::new(DayField::Status, 15.0),
+    ]
+}
+
+/// 解析“内容[,列宽[,表头]]”，多列用分号分隔，表头中的“\n”为换行，如 考勤,15;工时,8,{date}工时
+pub fn parse_day_columns(text: &str) -> Result<Vec<DayColumn>> {
+    let columns = text
+        .split(';')
+        .filter(|column| !column.trim().is_empty())
+        .map(|column| {
+            let mut parts = column.splitn(3, ',');
+            let name = parts.next().unwrap_or_default();
+            let field = DayField::from_name(name).ok_or(anyhow!(
+                "每日列内容填写有误：{name}，请填写{}",
+                DayField::NAMES.join("、")
+            ))?;
+            let width = match parts.next().map(str::trim) {
+                Some(width) if !width.is_empty() => width
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|width| *width >= 0.0)
+                    .ok_or(anyhow!("每日列宽度填写有误：{width}"))?,
+                _ => 0.0,
+            };
+            let header = match parts.next().map(str::trim) {
+                Some(header) if !header.is_empty() => header.replace("\\n", "\n"),
+                _ => field.default_header().to_string(),
+            };
+            render_header(&header, Date::MIN)?;
+            Ok(DayColumn {
+                field,
+                header,
+                width,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if columns.is_empty() {
+        return Err(anyhow!("每日列至少需要一列"));
+    }
+    Ok(columns)
+}
+
+/// 把表头模板中的占位符替换为这一天的值：
+///
+/// - `{date}`：日期，如“5月8日”
+/// - `{date:格式}`：按格式写日期，yyyy为四位年份、yy为两位年份、m和mm为月、d和dd为日，
+///   两个字母的补零，如`{date:yyyy-mm-dd}`为“2023-05-08”、`{date:m/d}`为“5/8”
+/// - `{weekday}`：星期，如“周一”
+/// - `{week}`：ISO周数，如“19”
+pub fn render_header(template: &str, date: Date) -> Result<String> {
+    let mut header = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        header.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or(anyhow!("表头中的“{{”没有对应的“}}”：{template}"))?
+            + start;
+        match &rest[start + 1..end] {
+            "date" => header.push_str(&format_date(date, "m月d日")),
+            "weekday" => {
+                const NAMES: [&str; 7] = ["一", "二", "三", "四", "五", "六", "日"];
+                header.push('周');
+                header.push_str(NAMES[date.weekday().number_days_from_monday() as usize]);
+            }
+            "week" => header.push_str(&date.iso_week().to_string()),
+            placeholder => match placeholder.strip_prefix("date:") {
+                Some(format) => header.push_str(&format_date(date, format)),
+                None => {
+                    return Err(anyhow!(
+                        "表头中的占位符有误：{{{placeholder}}}，可用{{date}}、{{date:格式}}、{{weekday}}、{{week}}"
+                    ))
+                }
+            },
+        }
+        rest = &rest[end + 1..];
+    }
+    header.push_str(rest);
+    Ok(header)
+}
+
+/// 按yyyy、yy、mm、m、dd、d组成的格式写日期，其他字符原样保留
+fn format_date(date: Date, format: &str) -> String {
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut count = 1;
+        while chars.peek() == Some(&c) && matches!(c, 'y' | 'm' | 'd') {
+            chars.next();
+            count += 1;
+        }
+        match (c, count) {
+            ('y', 2) => text.push_str(&format!("{:02}", date.year() % 100)),
+            ('y', _) => text.push_str(&date.year().to_string()),
+            ('m', 1) => text.push_str(&(date.month() as u8).to_string()),
+            ('m', _) => text.push_str(&format!("{:02}", date.month() as u8)),
+            ('d', 1) => text.push_str(&date.day().to_string()),
+            ('d', _) => text.push_str(&format!("{:02}", date.day())),
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+/// 工时的单位
+#[derive(Debug, Default, Clone, Copy, Readable, Writable, PartialEq)]
+pub enum WorkTimeUnit {
+    Minutes,
+    #[default]
+    Hours,
+    /// 人天，按每天的标准工时换算
+    Days,
+}
+
+impl WorkTimeUnit {
+    pub const ALL: [WorkTimeUnit; 3] = [
+        WorkTimeUnit::Minutes,
+        WorkTimeUnit::Hours,
+        WorkTimeUnit::Days,
+    ];
+
+    /// 单位的名称，也用于命令行参数和界面
+    pub const NAMES: [&'static str; 3] = ["分钟", "小时", "人天"];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let index = Self::NAMES.iter().position(|n| *n == name.trim())?;
+        Some(Self::ALL[index])
+    }
+}
+
+/// 工时的取整方式
+#[derive(Debug, Default, Clone, Copy, Readable, Writable, PartialEq)]
+pub enum Rounding {
+    /// 不取整，只按精度显示小数位数
+    #[default]
+    Off,
+    Nearest,
+    Down,
+    Up,
+}
+
+impl Rounding {
+    pub const ALL: [Rounding; 4] = [
+        Rounding::Off,
+        Rounding::Nearest,
+        Rounding::Down,
+        Rounding::Up,
+    ];
+
+    /// 取整方式的名称，也用于命令行参数和界面
+    pub const NAMES: [&'static str; 4] = ["不取整", "四舍五入", "向下取整", "向上取整"];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let index = Self::NAMES.iter().position(|n| *n == name.trim())?;
+        Some(Self::ALL[index])
+    }
+}
+
+/// 工时写入报表时的换算和取整
+#[derive(Debug, Clone, Readable, Writable, PartialEq)]
+pub struct WorkTime {
+    pub unit: WorkTimeUnit,
+    pub rounding: Rounding,
+    /// 取整到的精度，如0.5为取整到半个单位，同时决定显示的小数位数
+    pub precision: f64,
+    /// 每天的标准工时（小时），单位为人天时按它换算
+    pub standard_hours: f64,
+}
+
+impl Default for WorkTime {
+    /// 与早期版本一致：小时，不取整，显示两位小数
+    fn default() -> Self {
+        WorkTime {
+            unit: WorkTimeUnit::Hours,
+            rounding: Rounding::Off,
+            precision: 0.01,
+            standard_hours: 8.0,
+        }
+    }
+}
+
+impl WorkTime {
+    /// 检查精度和标准工时
+    pub fn check(&self) -> Result<()> {
+        if self.precision.is_nan() || self.precision <= 0.0 {
+            return Err(anyhow!("工时精度需大于0：{}", self.precision));
+        }
+        if self.standard_hours.is_nan() || self.standard_hours <= 0.0 {
+            return Err(anyhow!("每天标准工时需大于0：{}", self.standard_hours));
+        }
+        Ok(())
+    }
+
+    /// 把分钟数换算为设置的单位并取整
+    pub fn convert(&self, minutes: f64) -> f64 {
+        let value = match self.unit {
+            WorkTimeUnit::Minutes => minutes,
+            WorkTimeUnit::Hours => minutes / 60.0,
+            WorkTimeUnit::Days => minutes / 60.0 / self.standard_hours,
+        };
+        self.round(value)
+    }
+
+    /// 按取整方式和精度取整已经换算好的值
+    pub fn round(&self, value: f64) -> f64 {
+        // 留一点余量，避免0.3 / 0.1 = 2.9999999999999996这类误差影响向下、向上取整
+        let steps = value / self.precision;
+        let steps = match self.rounding {
+            Rounding::Off => return value,
+            Rounding::Nearest => steps.round(),
+            Rounding::Down => (steps + 1e-9).floor(),
+            Rounding::Up => (steps - 1e-9).ceil(),
+        };
+        let scale = 10_f64.powi(self.decimals() as i32);
+        (steps * self.precision * scale).round() / scale
+    }
+
+    /// 单元格的数字格式，小数位数与精度一致
+    pub fn number_format(&self) -> String {
+        match self.decimals() {
+            0 => "0".to_string(),
+            decimals => format!("0.{}", "0".repeat(decimals)),
+        }
+    }
+
+    fn decimals(&self) -> usize {
+        self.precision
+            .to_string()
+            .split_once('.')
+            .map_or(0, |(_, decimals)| decimals.len())
+    }
+}
+
+/// 报表单元格的样式
+#[derive(Debug, Default, Clone, Copy, Readable, Writable, PartialEq)]
+pub enum StylePreset {
+    /// 微软雅黑9号，水平垂直居中并自动换行
+    #[default]
+    Compact,
+    /// 沿用模板样式：表头参考表头行的工号单元格，数据参考同一行的工号单元格，只另设数字格式
+    Template,
+}
+
+impl StylePreset {
+    /// 预设的名称，也用于命令行参数和界面
+    pub const NAMES: [&'static str; 2] = ["微软雅黑9号居中", "沿用模板样式"];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match Self::NAMES.iter().position(|n| *n == name.trim())? {
+            0 => Some(StylePreset::Compact),
+            _ => Some(StylePreset::Template),
+        }
+    }
+
+    fn apply(self, style: &mut Style, reference: &Style) {
+        match self {
+            StylePreset::Compact => center_wrap(style),
+            StylePreset::Template => *style = reference.clone(),
+        }
+    }
+
+    /// 清空单元格时的样式：沿用模板样式时恢复为参考样式，否则保留原有的边框、填充等，只去掉高亮
+    fn clear(self, style: &mut Style, reference: &Style, highlights: &[HighlightRule]) {
+        match self {
+            StylePreset::Compact => apply_highlight(style, highlights, None),
+            StylePreset::Template => *style = reference.clone(),
+        }
+    }
+}
+
+fn center_wrap(style: &mut Style) {
+    let alignment = style.get_alignment_mut();
+    alignment.set_vertical(VerticalAlignmentValues::Center);
+    alignment.set_horizontal(HorizontalAlignmentValues::Center);
+    alignment.set_wrap_text(true);
+    style.get_font_mut().set_name("微软雅黑").set_size(9.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use time::macros::date;
+
+    use super::*;
+
+    fn fill_argb(style: &Style) -> Option<String> {
+        style
+            .get_background_color()
+            .map(|color| color.get_argb().to_string())
+    }
+
+    #[test]
+    fn stale_highlight_is_removed() {
+        let rules = highlight::default_rules();
+        let mut style = Style::default();
+        apply_highlight(&mut style, &rules, Some(&rules[0]));
+        assert_eq!(fill_argb(&style).as_deref(), Some("FFFFC7CE"));
+
+        // 重新生成时已恢复正常，上次的高亮应去掉
+        apply_highlight(&mut style, &rules, None);
+        assert_eq!(fill_argb(&style), None);
+        assert_eq!(style.get_font().unwrap().get_color().get_argb(), "FF000000");
+
+        // 换成另一条规则的颜色
+        apply_highlight(&mut style, &rules, Some(&rules[0]));
+        apply_highlight(&mut style, &rules, Some(&rules[1]));
+        assert_eq!(fill_argb(&style).as_deref(), Some("FFFFCC99"));
+    }
+
+    #[test]
+    fn template_colours_are_kept() {
+        let rules = highlight::default_rules();
+        let mut style = Style::default();
+        style.set_background_color("FFFFFF00");
+        style.get_font_mut().get_color_mut().set_argb("FF0070C0");
+        apply_highlight(&mut style, &rules, None);
+        assert_eq!(fill_argb(&style).as_deref(), Some("FFFFFF00"));
+        assert_eq!(style.get_font().unwrap().get_color().get_argb(), "FF0070C0");
+    }
+
+    #[test]
+    fn header_placeholders_across_year_end() {
+        let header = "{date:yy}年{date:m/d}（{weekday}）第{week}周";
+        // 2020年有53个ISO周，2021-01-03仍属于2020年第53周
+        let cases = [
+            (date!(2020 - 12 - 31), "20年12/31（周四）第53周"),
+            (date!(2021 - 01 - 03), "21年1/3（周日）第53周"),
+            (date!(2021 - 01 - 04), "21年1/4（周一）第1周"),
+        ];
+        for (date, expected) in cases {
+            assert_eq!(render_header(header, date).unwrap(), expected);
+        }
+        assert_eq!(
+            render_header("{date}\n{date:yyyy-mm-dd}", date!(2021 - 01 - 04)).unwrap(),
+            "1月4日\n2021-01-04"
+        );
+    }
+
+    fn work_time(rounding: Rounding, precision: f64) -> WorkTime {
+        WorkTime {
+            rounding,
+            precision,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rounding_at_float_edges() {
+        // 0.3 / 0.1 = 2.9999999999999996，0.7 / 0.1 = 6.999999999999999
+        for value in [0.3, 0.7, 1.1, 2.3] {
+            assert_eq!(work_time(Rounding::Down, 0.1).round(value), value);
+            assert_eq!(work_time(Rounding::Up, 0.1).round(value), value);
+            assert_eq!(work_time(Rounding::Nearest, 0.1).round(value), value);
+        }
+        assert_eq!(work_time(Rounding::Down, 0.1).round(0.39), 0.3);
+        assert_eq!(work_time(Rounding::Up, 0.1).round(0.31), 0.4);
+        assert_eq!(work_time(Rounding::Down, 0.5).round(7.99), 7.5);
+        assert_eq!(work_time(Rounding::Up, 0.5).round(7.01), 7.5);
+        assert_eq!(work_time(Rounding::Nearest, 0.25).round(7.12), 7.0);
+        assert_eq!(work_time(Rounding::Off, 0.1).round(7.123), 7.123);
+    }
+
+    #[test]
+    fn decimals_follow_precision() {
+        assert_eq!(work_time(Rounding::Off, 1.0).number_format(), "0");
+        assert_eq!(work_time(Rounding::Off, 0.5).number_format(), "0.0");
+        assert_eq!(work_time(Rounding::Off, 0.1).number_format(), "0.0");
+        assert_eq!(work_time(Rounding::Off, 0.25).number_format(), "0.00");
+        assert_eq!(work_time(Rounding::Off, 0.01).number_format(), "0.00");
+        // 分钟按30分钟取整
+        assert_eq!(work_time(Rounding::Down, 30.0).round(479.0), 450.0);
+    }
+
+    #[test]
+    fn unknown_placeholder_is_rejected() {
+        assert!(render_header("{day}", date!(2021 - 01 - 04)).is_err());
+        assert!(render_header("{date", date!(2021 - 01 - 04)).is_err());
+    }
+
+    #[test]
+    fn shorter_run_clears_columns_written_last_time() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let dir = env::temp_dir();
+        let template = dir.join(format!("liando_{}_template.xlsx", process::id()));
+        let first = dir.join(format!("liando_{}_first.xlsx", process::id()));
+        let second = dir.join(format!("liando_{}_second.xlsx", process::id()));
+
+        let mut book = umya_spreadsheet::new_file();
+        let worksheet = book.get_sheet_mut(&0).unwrap();
+        worksheet.get_cell_mut((1, 2)).set_value_string("工号");
+        worksheet.get_cell_mut((1, 3)).set_value_string("1001");
+        // 数据区域右边模板自带的列
+        worksheet.get_cell_mut((26, 3)).set_value_string("备注");
+        umya_spreadsheet::writer::xlsx::write(&book, &template).unwrap();
+
+        // 每天工时、考勤两列，从B列开始
+        let mut user_input = UserInput {
+            start_date: date!(2023 - 05 - 08).to_julian_day(),
+            end_date: date!(2023 - 05 - 12).to_julian_day(),
+            template_cfg: vec![SheetConfig::new(1, 2, 2)],
+            clear_block: true,
+            ..Default::default()
+        };
+        generate_report(&template, &first, &user_input, &db).unwrap();
+        user_input.end_date = date!(2023 - 05 - 09).to_julian_day();
+        generate_report(&first, &second, &user_input, &db).unwrap();
+
+        let book = umya_spreadsheet::reader::xlsx::read(&second).unwrap();
+        let worksheet = book.get_sheet(&0).unwrap();
+        assert_eq!(worksheet.get_formatted_value((5, 3)), "无数据");
+        for c in 6..12 {
+            for r in 2..4 {
+                assert_eq!(worksheet.get_formatted_value((c, r)), "", "({c}, {r})");
+            }
+        }
+        assert_eq!(worksheet.get_formatted_value((1, 3)), "1001");
+        assert_eq!(worksheet.get_formatted_value((26, 3)), "备注");
+
+        for path in [template, first, second] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    /// 有Sheet1、研发部、销售部三个sheet的模板
+    fn three_sheets() -> Spreadsheet {
+        let mut book = umya_spreadsheet::new_file();
+        book.new_sheet("研发部").unwrap();
+        book.new_sheet("销售部").unwrap();
+        book
+    }
+
+    fn named(sheet_name: &str) -> SheetConfig {
+        SheetConfig {
+            sheet_name: sheet_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sheets_by_name_and_order() {
+        let book = three_sheets();
+        assert_eq!(
+            resolve_sheets(&book, &[SheetConfig::default(), named("销售部")]).unwrap(),
+            vec![0, 2]
+        );
+        // 没填名称的按第几个配置对应第几个sheet
+        assert_eq!(
+            resolve_sheets(&book, &[named("销售部"), SheetConfig::default()]).unwrap(),
+            vec![2, 1]
+        );
+    }
+
+    #[test]
+    fn missing_sheet_name_is_rejected() {
+        let e = resolve_sheets(&three_sheets(), &[named("财务部")]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "模板中找不到名为“财务部”的sheet，现有的sheet：Sheet1、研发部、销售部"
+        );
+    }
+
+    #[test]
+    fn more_configs_than_sheets_is_rejected() {
+        let e = resolve_sheets(&three_sheets(), &vec![SheetConfig::default(); 4]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "模板只有3个sheet，第4个模板配置没有对应的sheet，请检查模板配置"
+        );
+    }
+
+    #[test]
+    fn same_sheet_twice_is_rejected() {
+        let e = resolve_sheets(&three_sheets(), &[SheetConfig::default(), named("Sheet1")])
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "第1个和第2个模板配置都对应sheet“Sheet1”，请检查模板配置"
+        );
+    }
+
+    #[test]
+    fn day_columns_with_width_and_header() {
+        let columns =
+            parse_day_columns(" 考勤,15; 工时 ,8,{date}（{weekday}）\\n工时;首次打卡;").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                DayColumn::new(DayField::Status, 15.0),
+                DayColumn {
+                    field: DayField::Hours,
+                    header: "{date}（{weekday}）\n工时".to_string(),
+                    width: 8.0,
+                },
+                DayColumn::new(DayField::FirstIn, 0.0),
+            ]
+        );
+        // 表头中可以有逗号
+        assert_eq!(
+            parse_day_columns("异常原因,,原因,说明").unwrap()[0].header,
+            "原因,说明"
+        );
+    }
+
+    #[test]
+    fn invalid_day_columns_are_rejected() {
+        for text in ["", " ; ", "出勤", "工时,宽", "工时,-1", "工时,8,{day}"] {
+            assert!(parse_day_columns(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use sled::Db;
+use speedy::{Readable, Writable};
+
+/// 指定数据库位置的环境变量，命令行参数`--db`优先
+pub const DB_ENV: &str = "LIANDO_DB";
+/// 旧版本的数据库名称，放在工作目录下
+const LEGACY_DB_NAME: &str = "liando.db";
+/// 旧数据库复制到数据目录后改成的名称
+const MIGRATED_DB_NAME: &str = "liando.db.已迁移";
+
+/// 某员工某天的考勤，以“{日期}_{工号}”为键存于sled
+///
+/// 增删字段需同时升级数据库版本，见[`crate::migrate`]
+#[derive(Debug, Default, Clone, Readable, Writable, PartialEq)]
+pub struct Attendance {
+    pub employee_id: String,
+    pub enter_info: String,
+    pub leave_info: String,
+    pub work_minutes: f64,
+    pub abnormal_reason: String,
+    /// 首次打卡时间，如 08:55，由原始打卡记录计算，没有时为空
+    pub first_in: String,
+    /// 末次打卡时间，同上
+    pub last_out: String,
+    /// 上下班打卡结果是按班次由打卡算出的，再导入打卡时可以重新计算
+    pub results_from_punches: bool,
+    /// 工作时长是由打卡算出的，之后导入每日统计表时不覆盖
+    pub minutes_from_punches: bool,
+}
+
+/// 默认的数据库位置：环境变量[`DB_ENV`]，否则为系统的用户数据目录下的`liando/liando.db`
+///
+/// 数据目录下还没有数据库而找到了旧版本的数据库时，把旧数据库复制过去，并把原来的改名为
+/// `liando.db.已迁移`；复制失败时继续在原处使用旧数据库。两处都有数据库时无法判断该用哪个，报错请用户指定
+pub fn default_path() -> Result<PathBuf> {
+    if let Some(path) = env::var_os(DB_ENV).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let legacy = legacy_path();
+    let Some(path) = data_dir().map(|dir| dir.join("liando").join("liando.db")) else {
+        return Ok(legacy.unwrap_or_else(|| PathBuf::from(LEGACY_DB_NAME)));
+    };
+    let Some(legacy) = legacy else {
+        return Ok(path);
+    };
+    if path.exists() {
+        return Err(anyhow!(
+            "在{}和{}都找到了考勤数据库，请用 --db 或环境变量{DB_ENV}指定要使用的数据库，\
+             并把不用的那个改名或删除",
+            legacy.display(),
+            path.display()
+        ));
+    }
+    match migrate_legacy(&legacy, &path) {
+        Ok(()) => Ok(path),
+        Err(_) => Ok(legacy),
+    }
+}
+
+/// 旧版本的数据库：工作目录或程序所在目录下的`liando.db`，从快捷方式启动时两者可能不同
+fn legacy_path() -> Option<PathBuf> {
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    [env::current_dir().ok(), exe_dir]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(LEGACY_DB_NAME))
+        .find(|path| path.is_dir())
+}
+
+/// 把旧数据库复制到新位置，再把旧的改名，以后不再找到它；中途失败时删掉复制了一半的数据库
+fn migrate_legacy(legacy: &Path, path: &Path) -> Result<()> {
+    let result = copy_dir(legacy, path)
+        .and_then(|()| fs::rename(legacy, legacy.with_file_name(MIGRATED_DB_NAME)));
+    if let Err(e) = result {
+        fs::remove_dir_all(path).ok();
+        return Err(anyhow!("迁移旧数据库失败：{e}"));
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// 系统的用户数据目录：Windows为%APPDATA%，macOS为~/Library/Application Support，其他为$XDG_DATA_HOME或~/.local/share
+fn data_dir() -> Option<PathBuf> {
+    let var = |key| {
+        env::var_os(key)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+    }
+}
+
+/// 打开考勤数据库，并把旧版本的数据升级到当前结构
+pub fn open(path: impl AsRef<Path>) -> Result<Db> {
+    let path = path.as_ref();
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("创建数据库目录{}失败：{e}", parent.display()))?;
+    }
+    let db = sled::open(path).map_err(|e| match e {
+        // sled用文件锁防止多个进程同时打开，拿不到锁时只给出一个笼统的io错误
+        sled::Error::Io(e)
+            if e.kind() == ErrorKind::Other && e.to_string().contains("acquire lock") =>
+        {
+            anyhow!(
+                "数据库{}正被另一个考勤助手使用，请先关闭其他考勤助手窗口或命令后重试",
+                path.display()
+            )
+        }
+        e => anyhow!("打开数据库{}失败：{e}", path.display()),
+    })?;
+    crate::migrate::run(&db)?;
+    Ok(db)
+}
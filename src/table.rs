@@ -0,0 +1,275 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use calamine::{open_workbook, Data, DataType, Reader, Xls};
+use encoding_rs::{Encoding, GBK, UTF_8};
+use time::{macros::format_description, Date, PrimitiveDateTime, Time};
+
+use crate::date::{from_serial, parse_date_cell, parse_datetime_cell};
+
+/// 导入用的表格数据，取自文件的第一个sheet，行列号与Excel一致从1开始
+#[derive(Debug, Default)]
+pub struct Table {
+    rows: Vec<Vec<Cell>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Cell {
+    text: String,
+    number: Option<f64>,
+}
+
+impl Table {
+    /// 按扩展名读取xlsx、xls或csv文件
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "xlsx" | "xlsm" => Self::read_xlsx(path),
+            "xls" => Self::read_xls(path),
+            "csv" => Self::read_csv(path),
+            _ => Err(anyhow!(
+                "不支持的文件格式：.{extension}，请使用xlsx、xls或csv文件"
+            )),
+        }
+    }
+
+    fn read_xlsx(path: &Path) -> Result<Self> {
+        let book = umya_spreadsheet::reader::xlsx::read(path)?;
+        let worksheet = book.get_sheet(&0).map_err(|e| anyhow!(e))?;
+        let (max_col, max_row) = worksheet.get_highest_column_and_row();
+
+        let rows = (1..max_row + 1)
+            .map(|r| {
+                (1..max_col + 1)
+                    .map(|c| Cell {
+                        text: worksheet.get_formatted_value((c, r)),
+                        number: worksheet.get_value_number((c, r)),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Table { rows })
+    }
+
+    fn read_xls(path: &Path) -> Result<Self> {
+        let mut book: Xls<_> = open_workbook(path).map_err(|e| anyhow!("读取xls文件失败：{e}"))?;
+        let range = book
+            .worksheet_range_at(0)
+            .ok_or(anyhow!("xls文件中没有sheet"))?
+            .map_err(|e| anyhow!("读取xls文件失败：{e}"))?;
+
+        // range只包含有数据的区域，左上角不一定是A1
+        let (start_row, start_col) = range.start().unwrap_or_default();
+        let mut rows = vec![Vec::new(); start_row as usize];
+        for data_row in range.rows() {
+            let mut row = vec![Cell::default(); start_col as usize];
+            row.extend(data_row.iter().map(|data| match data {
+                Data::DateTime(datetime) if datetime.is_datetime() => Cell {
+                    text: format_serial_date(datetime.as_f64()),
+                    number: Some(datetime.as_f64()),
+                },
+                _ => Cell {
+                    text: data.to_string(),
+                    number: data.get_float().or(data.get_int().map(|int| int as f64)),
+                },
+            }));
+            rows.push(row);
+        }
+        Ok(Table { rows })
+    }
+
+    fn read_csv(path: &Path) -> Result<Self> {
+        Self::parse_csv(&decode(&fs::read(path)?))
+    }
+
+    fn parse_csv(content: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(content.as_bytes());
+
+        let mut rows = Vec::new();
+        // csv会跳过空行，按记录所在行号补上，保证行号和Excel打开时一致
+        let mut next_line = 1;
+        for record in reader.records() {
+            let record = record.map_err(|e| {
+                let line = e
+                    .position()
+                    .map_or(next_line, |pos| start_line(content, pos));
+                anyhow!("CSV第{line}行格式有误：{e}")
+            })?;
+            let line = record
+                .position()
+                .map_or(next_line, |pos| start_line(content, pos));
+            while next_line < line {
+                rows.push(Vec::new());
+                next_line += 1;
+            }
+            next_line = line
+                + 1
+                + record
+                    .iter()
+                    .map(|f| f.matches('\n').count() as u64)
+                    .sum::<u64>();
+            rows.push(
+                record
+                    .iter()
+                    .map(|field| Cell {
+                        text: field.to_string(),
+                        number: field.trim().parse::<f64>().ok(),
+                    })
+                    .collect(),
+            );
+        }
+        Ok(Table { rows })
+    }
+
+    /// 最后一行的行号
+    pub fn get_highest_row(&self) -> u32 {
+        self.rows.len() as u32
+    }
+
+    /// 整行没有任何内容
+    pub fn is_row_empty(&self, row: u32) -> bool {
+        (row as usize)
+            .checked_sub(1)
+            .and_then(|index| self.rows.get(index))
+            .is_none_or(|cells| cells.iter().all(|cell| cell.text.trim().is_empty()))
+    }
+
+    /// 在某一行中查找文本（忽略首尾空白）完全相同的单元格，返回列号
+    pub fn find_col(&self, row: u32, text: &str) -> Option<u32> {
+        let cells = self.rows.get((row as usize).checked_sub(1)?)?;
+        cells
+            .iter()
+            .position(|cell| cell.text.trim() == text.trim())
+            .map(|index| index as u32 + 1)
+    }
+
+    /// 单元格的日期，见[`parse_date_cell`]，无法识别为None
+    pub fn get_date(&self, (col, row): (u32, u32)) -> Option<Date> {
+        self.cell(col, row)
+            .and_then(|cell| parse_date_cell(&cell.text, cell.number))
+    }
+
+    /// 单元格的日期时间，见[`parse_datetime_cell`]，无法识别为None
+    pub fn get_datetime(&self, (col, row): (u32, u32)) -> Option<PrimitiveDateTime> {
+        self.cell(col, row)
+            .and_then(|cell| parse_datetime_cell(&cell.text, cell.number))
+    }
+
+    /// 单元格显示的文本，超出范围为空
+    pub fn get_formatted_value(&self, (col, row): (u32, u32)) -> String {
+        self.cell(col, row)
+            .map(|cell| cell.text.clone())
+            .unwrap_or_default()
+    }
+
+    /// 单元格的数值，非数字为None
+    pub fn get_value_number(&self, (col, row): (u32, u32)) -> Option<f64> {
+        self.cell(col, row).and_then(|cell| cell.number)
+    }
+
+    fn cell(&self, col: u32, row: u32) -> Option<&Cell> {
+        self.rows
+            .get((row as usize).checked_sub(1)?)?
+            .get((col as usize).checked_sub(1)?)
+    }
+}
+
+/// 记录所在的行号。csv给出的位置是上一条记录结束的地方，还要加上之后跳过的空行
+fn start_line(content: &str, position: &csv::Position) -> u64 {
+    let skipped = content.as_bytes()[position.byte() as usize..]
+        .iter()
+        .take_while(|&&byte| matches!(byte, b'\r' | b'\n'))
+        .filter(|&&byte| byte == b'\n')
+        .count();
+    position.line() + skipped as u64
+}
+
+/// 考勤系统导出的csv可能是UTF-8（可带BOM）或GBK，有BOM按BOM，否则先试UTF-8再按GBK解码
+fn decode(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return encoding
+            .decode_without_bom_handling(&bytes[bom_len..])
+            .0
+            .into_owned();
+    }
+    match UTF_8.decode_without_bom_handling_and_without_replacement(bytes) {
+        Some(content) => content.into_owned(),
+        None => GBK.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+/// 把Excel日期序列号显示为“年-月-日”，带时间的再加“时:分:秒”
+fn format_serial_date(serial: f64) -> String {
+    match from_serial(serial) {
+        Some(datetime) if datetime.time() == Time::MIDNIGHT => datetime
+            .date()
+            .format(format_description!("[year]-[month]-[day]"))
+            .unwrap_or_default(),
+        Some(datetime) => datetime
+            .format(format_description!(
+                "[year]-[month]-[day] [hour]:[minute]:[second]"
+            ))
+            .unwrap_or_default(),
+        None => serial.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(table: &Table) -> Vec<Vec<String>> {
+        (1..table.get_highest_row() + 1)
+            .map(|r| {
+                let cells = table.rows[r as usize - 1].len() as u32;
+                (1..cells + 1)
+                    .map(|c| table.get_formatted_value((c, r)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_utf8_with_and_without_bom() {
+        assert_eq!(decode("工号,日期".as_bytes()), "工号,日期");
+        assert_eq!(decode("\u{feff}工号,日期".as_bytes()), "工号,日期");
+    }
+
+    #[test]
+    fn decode_falls_back_to_gbk() {
+        let (bytes, _, _) = GBK.encode("工号,日期\n1001,2023-05-08 星期一");
+        assert!(std::str::from_utf8(&bytes).is_err());
+        assert_eq!(decode(&bytes), "工号,日期\n1001,2023-05-08 星期一");
+    }
+
+    #[test]
+    fn empty_lines_keep_row_numbers() {
+        let table = Table::parse_csv("\n\n工号,备注\n\n1001,\"两行\n说明\"\n1002,正常\r\n\r\n1003")
+            .unwrap();
+        assert_eq!(
+            texts(&table),
+            vec![
+                vec![],
+                vec![],
+                vec!["工号".to_string(), "备注".to_string()],
+                vec![],
+                vec!["1001".to_string(), "两行\n说明".to_string()],
+                // 带换行的单元格在Excel中只占一行
+                vec!["1002".to_string(), "正常".to_string()],
+                vec![],
+                vec!["1003".to_string()],
+            ]
+        );
+        assert!(table.is_row_empty(4));
+        assert_eq!(table.find_col(3, " 备注 "), Some(2));
+        assert_eq!(table.get_value_number((1, 8)), Some(1003.0));
+    }
+}